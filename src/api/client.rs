@@ -0,0 +1,201 @@
+// Client for using the API as a library, without the Config of the app:
+//
+//     let client = ClockifyClient::builder().api_key(key).build()?;
+//     let projects = client.workspace(&id).projects().list(None)?;
+//
+// Each scope carries the ids its endpoints need, so items are requested the
+// same way the app requests them through EndPoint.
+
+use std::{marker::PhantomData, time::Duration};
+
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Client, Method};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    api::{
+        ApiRequest,
+        EndPoint,
+        EndpointParameters,
+        project::Project,
+        tag::Tag,
+        task::Task,
+        time_entry::TimeEntry,
+        user::User,
+        workspace::Workspace,
+    },
+    clockify::Config,
+    error::Error,
+};
+
+pub struct ClockifyClientBuilder {
+    base_url: String,
+    api_key: Option<String>,
+    timeout: Option<Duration>,
+    user_agent: String,
+}
+
+impl Default for ClockifyClientBuilder {
+    fn default() -> ClockifyClientBuilder {
+        ClockifyClientBuilder {
+            base_url: Config::default().base_url,
+            api_key: None,
+            timeout: None,
+            user_agent: format!("clockify/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+impl ClockifyClientBuilder {
+    pub fn base_url(mut self, base_url: impl Into<String>) -> ClockifyClientBuilder {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> ClockifyClientBuilder {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> ClockifyClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> ClockifyClientBuilder {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn build(self) -> Result<ClockifyClient, Error> {
+        let api_key = self.api_key.ok_or(Error::MissingApiKey)?;
+        let mut http = Client::builder().user_agent(self.user_agent);
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        Ok(ClockifyClient {
+            http: http.build()?,
+            config: Config {
+                base_url: self.base_url.trim_end_matches('/').to_owned(),
+                api_key: Some(api_key),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+pub struct ClockifyClient {
+    http: Client,
+    // Only the base url, api key and the ids of the current scope are used
+    config: Config,
+}
+
+impl ClockifyClient {
+    pub fn builder() -> ClockifyClientBuilder {
+        ClockifyClientBuilder::default()
+    }
+
+    // The user the API key belongs to
+    pub fn user(&self) -> Result<User, Error> {
+        let request = ApiRequest {
+            method: Method::GET,
+            url: format!("{}/user", self.config.base_url),
+            api_key: self.config.api_key.clone().ok_or(Error::MissingApiKey)?,
+            body: None,
+        };
+        Ok(request.send(&self.http)?.json::<User>()?)
+    }
+
+    pub fn workspaces(&self) -> Resource<'_, Workspace> {
+        Resource::new(self, self.config.clone())
+    }
+
+    pub fn workspace(&self, id: &str) -> WorkspaceScope<'_> {
+        WorkspaceScope {
+            client: self,
+            config: Config { workspace_id: Some(id.to_owned()), ..self.config.clone() },
+        }
+    }
+}
+
+pub struct WorkspaceScope<'a> {
+    client: &'a ClockifyClient,
+    config: Config,
+}
+
+impl<'a> WorkspaceScope<'a> {
+    pub fn projects(&self) -> Resource<'a, Project> {
+        Resource::new(self.client, self.config.clone())
+    }
+
+    pub fn project(&self, id: &str) -> ProjectScope<'a> {
+        ProjectScope {
+            client: self.client,
+            config: Config { project_id: Some(id.to_owned()), ..self.config.clone() },
+        }
+    }
+
+    pub fn tags(&self) -> Resource<'a, Tag> {
+        Resource::new(self.client, self.config.clone())
+    }
+
+    pub fn users(&self) -> Resource<'a, User> {
+        Resource::new(self.client, self.config.clone())
+    }
+
+    // Time entries are listed and created per user
+    pub fn time_entries(&self, user_id: &str) -> Resource<'a, TimeEntry> {
+        Resource::new(self.client, Config { user_id: Some(user_id.to_owned()), ..self.config.clone() })
+    }
+}
+
+pub struct ProjectScope<'a> {
+    client: &'a ClockifyClient,
+    config: Config,
+}
+
+impl<'a> ProjectScope<'a> {
+    pub fn tasks(&self) -> Resource<'a, Task> {
+        Resource::new(self.client, self.config.clone())
+    }
+}
+
+// The requests of one endpoint within a scope
+pub struct Resource<'a, T> {
+    client: &'a ClockifyClient,
+    config: Config,
+    item: PhantomData<T>,
+}
+
+impl<'a, T: EndPoint + Serialize + DeserializeOwned> Resource<'a, T> {
+    fn new(client: &'a ClockifyClient, config: Config) -> Resource<'a, T> {
+        Resource { client, config, item: PhantomData }
+    }
+
+    pub fn list(&self, params: Option<EndpointParameters>) -> Result<Vec<T>, Error> {
+        T::list(&self.client.http, &self.config, params)
+    }
+
+    pub fn get(&self, id: &str) -> Result<T, Error> {
+        T::get(&self.client.http, &self.config, id, None)
+    }
+
+    pub fn create(&self, item: T) -> Result<T, Error> {
+        item.create(&self.client.http, &self.config, None)
+    }
+
+    pub fn update(&self, id: &str, item: T) -> Result<T, Error> {
+        T::update(item, &self.client.http, &self.config, id, None)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), Error> {
+        T::delete(&self.client.http, &self.config, id, None)
+    }
+}
+
+impl Resource<'_, TimeEntry> {
+    // Stop the running timer of the user, None when none was running
+    pub fn stop(&self, end: DateTime<Utc>) -> Result<Option<TimeEntry>, Error> {
+        TimeEntry::stop(&self.client.http, &self.config, end)
+    }
+}
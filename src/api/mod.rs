@@ -1,28 +1,131 @@
 pub mod tag; 
 pub mod project; 
+pub mod client; 
 pub mod common; 
+pub mod nonblocking; 
 pub mod task;
 pub mod time_entry; 
 pub mod user;
 pub mod workspace; 
 
-use std::collections::HashMap;
 use std::fmt;
-use reqwest::blocking::{Client, RequestBuilder}; 
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{
+    blocking::{Client, RequestBuilder, Response}, 
+    header::{HeaderMap, RETRY_AFTER}, 
+    Method, 
+    StatusCode, 
+};
 
-use serde::Serialize; 
+use serde::{de::DeserializeOwned, Deserialize, Serialize}; 
 use crate::{
     error::Error,
     clockify::Config, 
 };
 
-pub type EndpointParameters = HashMap<String, ParameterValue>;
+// Identifies items in lists and requests
+pub trait Id {
+    fn id(&self) -> String; 
+}
+
+// Characters left as they are in query keys and values, as in RFC 3986
+const QUERY_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
 
-#[derive(Debug, Clone)]
+// Query parameters, kept in the order they were added. A key can be repeated,
+// e.g. to filter by several tags.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointParameters {
+    params: Vec<(String, ParameterValue)>, 
+}
+
+impl EndpointParameters {
+    pub fn new() -> EndpointParameters {
+        EndpointParameters::default()
+    }
+
+    // Set a parameter, replacing any earlier value of the key
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<ParameterValue>) {
+        let key = key.into();
+        self.params.retain(|(k, _)| *k != key);
+        self.params.push((key, value.into()));
+    }
+
+    // Add a parameter, keeping earlier values of the key
+    pub fn append(&mut self, key: impl Into<String>, value: impl Into<ParameterValue>) {
+        self.params.push((key.into(), value.into()));
+    }
+
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<ParameterValue>) -> EndpointParameters {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ParameterValue> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    // Percent-encoded "?key=value&..." with a pair for each item of a list,
+    // empty without parameters
+    pub fn to_query(&self) -> String {
+        let mut pairs = vec![];
+        for (key, value) in &self.params {
+            let values = match value {
+                ParameterValue::List(values) => values.iter().collect(), 
+                value => vec![value], 
+            };
+            for value in values {
+                pairs.push(format!("{}={}", 
+                    utf8_percent_encode(key, QUERY_ENCODE), 
+                    utf8_percent_encode(&value.to_string(), QUERY_ENCODE)
+                ));
+            }
+        }
+        if pairs.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", pairs.join("&"))
+        }
+    }
+}
+
+// Enums sent as query values, e.g. SortOrder
+pub trait ParameterEnum {
+    fn value(&self) -> &'static str; 
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Ascending, 
+    Descending, 
+}
+
+impl ParameterEnum for SortOrder {
+    fn value(&self) -> &'static str {
+        match self {
+            SortOrder::Ascending => "ASCENDING", 
+            SortOrder::Descending => "DESCENDING", 
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParameterValue {
     String(String),
     Boolean(bool),
-    Integer(u32)
+    Integer(u32), 
+    Date(DateTime<Utc>), 
+    Enum(&'static str), 
+    // Sent as the key repeated for every item
+    List(Vec<ParameterValue>), 
 }
 
 impl fmt::Display for ParameterValue {
@@ -43,6 +146,16 @@ impl fmt::Display for ParameterValue {
             }, 
             ParameterValue::Integer(i) => {
                 write!(f, "{}", i)
+            }, 
+            ParameterValue::Date(d) => {
+                write!(f, "{}", time_entry::format_timestamp(*d))
+            }, 
+            ParameterValue::Enum(e) => {
+                write!(f, "{}", e)
+            }, 
+            ParameterValue::List(values) => {
+                let values : Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{}", values.join(","))
             }
         }
     }
@@ -66,28 +179,180 @@ impl From<String> for ParameterValue {
     }
 }
 
-#[derive(Debug, Clone)]
+impl From<&str> for ParameterValue {
+    fn from(s: &str) -> ParameterValue {
+        ParameterValue::String(s.to_owned())
+    }
+}
+
+impl From<DateTime<Utc>> for ParameterValue {
+    fn from(d: DateTime<Utc>) -> ParameterValue {
+        ParameterValue::Date(d)
+    }
+}
+
+impl From<SortOrder> for ParameterValue {
+    fn from(e: SortOrder) -> ParameterValue {
+        ParameterValue::Enum(e.value())
+    }
+}
+
+impl<T: Into<ParameterValue>> From<Vec<T>> for ParameterValue {
+    fn from(values: Vec<T>) -> ParameterValue {
+        ParameterValue::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+// Body of Clockify's error responses
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApiMessage {
+    pub message: String, 
+    #[serde(default)]
+    pub code: u32, 
+}
+
+impl fmt::Display for ApiMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum EndpointError {
+    BadRequest(Option<ApiMessage>), // 400
     Unauthorized, // 401 
     Forbidden, // 403
     NotFound, // 404
-    Unknown(String)
+    Conflict(Option<ApiMessage>), // 409
+    // 429, with the seconds of the Retry-After header
+    TooManyRequests(Option<u64>), 
+    Server(u16, Option<ApiMessage>), // 5xx
+    // Any other status that is not a success
+    Status(u16, Option<ApiMessage>), 
+    // The response was not the expected JSON
+    Decode(String), 
+    // No response, e.g. no connection or a timeout
+    Request(String), 
+}
+
+impl EndpointError {
+    pub fn from_status(status: StatusCode, retry_after: Option<u64>, body: &str) -> EndpointError {
+        let message = serde_json::from_str::<ApiMessage>(body).ok();
+        match status.as_u16() {
+            400 => EndpointError::BadRequest(message), 
+            401 => EndpointError::Unauthorized, 
+            403 => EndpointError::Forbidden, 
+            404 => EndpointError::NotFound, 
+            409 => EndpointError::Conflict(message), 
+            429 => EndpointError::TooManyRequests(retry_after), 
+            s if status.is_server_error() => EndpointError::Server(s, message), 
+            s => EndpointError::Status(s, message), 
+        }
+    }
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<u64> {
+    headers.get(RETRY_AFTER)?.to_str().ok()?.parse().ok()
+}
+
+// The response when its status is a success, otherwise the error described
+// by its status and body
+pub fn check_status(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = retry_after(response.headers());
+    let body = response.text().unwrap_or_default();
+    Err(Error::Api(EndpointError::from_status(status, retry_after, &body)))
+}
+
+pub async fn check_status_async(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = retry_after(response.headers());
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Api(EndpointError::from_status(status, retry_after, &body)))
 }
 
 impl From<reqwest::Error> for EndpointError {
     fn from(error: reqwest::Error) -> EndpointError {
+        if error.is_decode() {
+            return EndpointError::Decode(error.to_string());
+        }
         match error.status() {
-            Some(s) => {
-                match s.as_u16() {
-                    401 => EndpointError::Unauthorized, 
-                    403 => EndpointError::Forbidden, 
-                    404 => EndpointError::NotFound, 
-                    _ => EndpointError::Unknown(format!("{:?}", error))
-                }
-            }, 
-            None => {
-                EndpointError::Unknown(format!("{:?}", error))
-            }
+            Some(s) => EndpointError::from_status(s, None, ""), 
+            None => EndpointError::Request(error.to_string()), 
+        }
+    }
+}
+
+fn with_message(f: &mut fmt::Formatter, text: &str, message: &Option<ApiMessage>) -> fmt::Result {
+    match message {
+        Some(m) => write!(f, "{}: {}", text, m), 
+        None => write!(f, "{}", text), 
+    }
+}
+
+impl fmt::Display for EndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EndpointError::BadRequest(m) => with_message(f, "Bad request", m), 
+            EndpointError::Unauthorized => write!(f, "The API key was not accepted"), 
+            EndpointError::Forbidden => write!(f, "Not allowed for this API key"), 
+            EndpointError::NotFound => write!(f, "Not found"), 
+            EndpointError::Conflict(m) => with_message(f, "Conflict", m), 
+            EndpointError::TooManyRequests(Some(seconds)) => write!(f, "Too many requests, retry in {} seconds", seconds), 
+            EndpointError::TooManyRequests(None) => write!(f, "Too many requests"), 
+            EndpointError::Server(status, m) => with_message(f, &format!("Clockify server error {}", status), m), 
+            EndpointError::Status(status, m) => with_message(f, &format!("Unexpected response {}", status), m), 
+            EndpointError::Decode(e) => write!(f, "Could not read the response: {}", e), 
+            EndpointError::Request(e) => write!(f, "Request failed: {}", e), 
+        }
+    }
+}
+
+impl std::error::Error for EndpointError {}
+
+// Method, URL and body of a request, built once by EndPoint and sent by
+// either the blocking or the async client
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    pub method: Method, 
+    pub url: String, 
+    pub api_key: String, 
+    pub body: Option<serde_json::Value>, 
+}
+
+impl ApiRequest {
+    pub fn blocking(&self, client: &Client) -> RequestBuilder {
+        let request = client
+            .request(self.method.clone(), &self.url)
+            .header("X-API-KEY", &self.api_key);
+        match &self.body {
+            Some(body) => request.json(body), 
+            None => request, 
+        }
+    }
+
+    // Send and check the status
+    pub fn send(&self, client: &Client) -> Result<Response, Error> {
+        check_status(self.blocking(client).send()?)
+    }
+
+    pub async fn send_async(&self, client: &reqwest::Client) -> Result<reqwest::Response, Error> {
+        check_status_async(self.nonblocking(client).send().await?).await
+    }
+
+    pub fn nonblocking(&self, client: &reqwest::Client) -> reqwest::RequestBuilder {
+        let request = client
+            .request(self.method.clone(), &self.url)
+            .header("X-API-KEY", &self.api_key);
+        match &self.body {
+            Some(body) => request.json(body), 
+            None => request, 
         }
     }
 }
@@ -96,96 +361,166 @@ pub trait EndPoint {
 
     fn endpoint(config: &Config) -> Result<String, Error>;
 
+    // Base path for requests on a single item, when it differs from endpoint()
+    fn item_endpoint(config: &Config) -> Result<String, Error> {
+        Self::endpoint(config)
+    }
+
     fn add_params(params: EndpointParameters) -> String {
-        let mut output = String::new(); 
-        for (i, (key, value)) in params.into_iter().enumerate() {
-            if i == 0 {
-                output = format!("?{}={}", key, value);
-            } else {
-                output = format!("&{}={}", key, value); 
-            }
-        }
-        output
+        params.to_query()
     }
 
     fn format_url(id: Option<&str>, params: Option<EndpointParameters>, config: &Config) -> Result<String, Error> {
-        let mut url = format!("{}{}", config.base_url, Self::endpoint(config)?); 
-        if let Some(i) = id {
-            url = format!("{}/{}", url, i); 
-        }
+        let mut url = match id {
+            Some(i) => format!("{}{}/{}", config.base_url, Self::item_endpoint(config)?, i), 
+            None => format!("{}{}", config.base_url, Self::endpoint(config)?), 
+        };
         if let Some(p) = params {
             url = format!("{}{}", url, Self::add_params(p)); 
         }
         Ok(url)
     }
 
-    fn set_api_key(request: RequestBuilder, config: &Config) -> RequestBuilder {
-        request.header("X-API-KEY", config.api_key.as_ref().unwrap().clone())
+    fn request<T: Serialize>(method: Method, id: Option<&str>, params: Option<EndpointParameters>, config: &Config, body: Option<&T>) -> Result<ApiRequest, Error> {
+        Ok(ApiRequest {
+            method, 
+            url: Self::format_url(id, params, config)?, 
+            api_key: config.api_key.clone().ok_or(Error::MissingApiKey)?, 
+            body: body.map(serde_json::to_value).transpose()?, 
+        })
     }
 
     fn create(self, client: &Client, config: &Config, params: Option<EndpointParameters>) -> Result<Self, Error>
-        where Self: Sized + Serialize, for <'de> Self: serde::de::Deserialize<'de> {
-            let url : String = Self::format_url(None, params, config)?; 
-            let request : RequestBuilder = Self::set_api_key(client.post(url), config);
-            let response = request
-                .json(&self)
-                .send()?
+        where Self: Sized + Serialize + DeserializeOwned {
+            let response = Self::request(Method::POST, None, params, config, Some(&self))?
+                .send(client)?
                 .json::<Self>()?; 
             Ok(response)
     }
 
     fn patch(data: Self, client: &Client, config: &Config, params: Option<EndpointParameters>) -> Result<Self, Error> 
-        where Self: Sized + Serialize, for <'de> Self: serde::de::Deserialize<'de> {
-            let url : String = Self::format_url(None, params, config)?; 
-            let request : RequestBuilder = Self::set_api_key(client.patch(url), config);
-            let response = request
-                .json(&data)
-                .send()?
+        where Self: Sized + Serialize + DeserializeOwned {
+            let response = Self::request(Method::PATCH, None, params, config, Some(&data))?
+                .send(client)?
                 .json::<Self>()?; 
             Ok(response)
     }
 
-    fn update(data: Self, client: &Client, config: &Config, params: Option<EndpointParameters>) -> Result<Self, Error> 
-        where Self: Sized + Serialize, for <'de> Self: serde::de::Deserialize<'de> {
-            let url : String = Self::format_url(None, params, config)?; 
-            let request : RequestBuilder = Self::set_api_key(client.put(url), config);
-            let response = request
-                .json(&data)
-                .send()?
+    fn update(data: Self, client: &Client, config: &Config, id: &str, params: Option<EndpointParameters>) -> Result<Self, Error> 
+        where Self: Sized + Serialize + DeserializeOwned {
+            let response = Self::request(Method::PUT, Some(id), params, config, Some(&data))?
+                .send(client)?
                 .json::<Self>()?; 
             Ok(response)
     }
 
     fn list(client: &Client, config: &Config, params: Option<EndpointParameters>) -> Result<Vec<Self>, Error>  
-        where Self: Sized, for <'de> Self: serde::de::Deserialize<'de> {
-        let url : String = Self::format_url(None, params, config)?; 
-        let request : RequestBuilder = Self::set_api_key(client.get(url), config);
-        let response = request
-            .send()?
+        where Self: Sized + DeserializeOwned {
+        let response = Self::request::<()>(Method::GET, None, params, config, None)?
+            .send(client)?
             .json::<Vec<Self>>()?; 
         Ok(response)
     }
 
     fn get(client: &Client, config: &Config, id: &str, params: Option<EndpointParameters>) -> Result<Self, Error>
-        where Self: Sized, for <'de> Self: serde::de::Deserialize<'de> {
-        let url : String = Self::format_url(Some(id), params, config)?; 
-        let request : RequestBuilder = Self::set_api_key(client.get(url), config);
-        let response = request
-            .send()?
+        where Self: Sized + DeserializeOwned {
+        let response = Self::request::<()>(Method::GET, Some(id), params, config, None)?
+            .send(client)?
             .json::<Self>()?; 
         Ok(response)
 
     }
     
+    fn delete(client: &Client, config: &Config, id: &str, params: Option<EndpointParameters>) -> Result<(), Error> {
+        Self::request::<()>(Method::DELETE, Some(id), params, config, None)?
+            .send(client)?;
+        Ok(())
+    }
+    
     fn add(&self, client: &Client, config: &Config) -> Result<(), Error> 
-        where Self: Sized, for <'de> Self: serde::de::Deserialize<'de>, Self: Serialize {
-        let url : String = Self::format_url(None, None, config)?;
-        let request : RequestBuilder = Self::set_api_key(client.post(url), config);
-        let _response = request
-            .json(self)
-            .send()?
+        where Self: Sized + Serialize + DeserializeOwned {
+        let _response = Self::request(Method::POST, None, None, config, Some(self))?
+            .send(client)?
             .json::<Self>()?;
         Ok(())
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn values() {
+        assert_eq!(ParameterValue::from("name").to_string(), "name");
+        assert_eq!(ParameterValue::from(String::from("name")).to_string(), "name");
+        assert_eq!(ParameterValue::from(true).to_string(), "true");
+        assert_eq!(ParameterValue::from(false).to_string(), "false");
+        assert_eq!(ParameterValue::from(50).to_string(), "50");
+        let date = Utc.with_ymd_and_hms(2024, 3, 13, 9, 5, 0).unwrap();
+        assert_eq!(ParameterValue::from(date).to_string(), "2024-03-13T09:05:00Z");
+        assert_eq!(ParameterValue::from(SortOrder::Descending).to_string(), "DESCENDING");
+        assert_eq!(ParameterValue::from(vec!["a", "b"]).to_string(), "a,b");
+    }
+
+    #[test]
+    fn query() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 13, 9, 5, 0).unwrap();
+        let params = EndpointParameters::new()
+            .with("description", "fix bug #12 & more")
+            .with("hydrated", true)
+            .with("page-size", 1000)
+            .with("start", date)
+            .with("sort-order", SortOrder::Ascending);
+        assert_eq!(
+            params.to_query(),
+            "?description=fix%20bug%20%2312%20%26%20more&hydrated=true&page-size=1000\
+             &start=2024-03-13T09%3A05%3A00Z&sort-order=ASCENDING"
+        );
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let mut params = EndpointParameters::new().with("tags", vec!["a", "b"]);
+        params.append("tags", "c");
+        assert_eq!(params.to_query(), "?tags=a&tags=b&tags=c");
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let mut params = EndpointParameters::new();
+        params.insert("page", 1);
+        params.insert("page", 2);
+        assert_eq!(params.get("page"), Some(&ParameterValue::Integer(2)));
+        assert_eq!(params.to_query(), "?page=2");
+    }
+
+    #[test]
+    fn empty() {
+        let params = EndpointParameters::new();
+        assert!(params.is_empty());
+        assert_eq!(params.to_query(), "");
+        assert_eq!(EndpointParameters::new().with("tags", Vec::<String>::new()).to_query(), "");
+    }
+
+    #[test]
+    fn error_responses() {
+        let body = r#"{"message":"Project name already exists","code":501}"#;
+        let error = EndpointError::from_status(StatusCode::BAD_REQUEST, None, body);
+        assert_eq!(error.to_string(), "Bad request: Project name already exists (code 501)");
+        assert_eq!(
+            EndpointError::from_status(StatusCode::CONFLICT, None, "not json"), 
+            EndpointError::Conflict(None)
+        );
+        assert_eq!(
+            EndpointError::from_status(StatusCode::TOO_MANY_REQUESTS, Some(2), ""), 
+            EndpointError::TooManyRequests(Some(2))
+        );
+        assert_eq!(
+            EndpointError::from_status(StatusCode::BAD_GATEWAY, None, "").to_string(), 
+            "Clockify server error 502"
+        );
+        assert_eq!(EndpointError::from_status(StatusCode::UNAUTHORIZED, None, body), EndpointError::Unauthorized);
+    }
+}
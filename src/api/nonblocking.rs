@@ -0,0 +1,66 @@
+// Async versions of the EndPoint methods, for use with reqwest's async client
+// from tokio code. Every EndPoint gets them through the blanket impl; the
+// requests are built by EndPoint::request exactly like the blocking ones.
+
+use std::future::Future;
+
+use reqwest::{Client, Method};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    api::{EndPoint, EndpointParameters},
+    clockify::Config,
+    error::Error,
+};
+
+pub trait AsyncEndPoint: EndPoint {
+    fn create_async(self, client: &Client, config: &Config, params: Option<EndpointParameters>) -> impl Future<Output = Result<Self, Error>> + Send
+        where Self: Sized + Serialize + DeserializeOwned + Send {
+        async move {
+            let request = Self::request(Method::POST, None, params, config, Some(&self))?;
+            Ok(request.send_async(client).await?.json::<Self>().await?)
+        }
+    }
+
+    fn patch_async(data: Self, client: &Client, config: &Config, params: Option<EndpointParameters>) -> impl Future<Output = Result<Self, Error>> + Send
+        where Self: Sized + Serialize + DeserializeOwned + Send {
+        async move {
+            let request = Self::request(Method::PATCH, None, params, config, Some(&data))?;
+            Ok(request.send_async(client).await?.json::<Self>().await?)
+        }
+    }
+
+    fn update_async(data: Self, client: &Client, config: &Config, id: &str, params: Option<EndpointParameters>) -> impl Future<Output = Result<Self, Error>> + Send
+        where Self: Sized + Serialize + DeserializeOwned + Send {
+        async move {
+            let request = Self::request(Method::PUT, Some(id), params, config, Some(&data))?;
+            Ok(request.send_async(client).await?.json::<Self>().await?)
+        }
+    }
+
+    fn list_async(client: &Client, config: &Config, params: Option<EndpointParameters>) -> impl Future<Output = Result<Vec<Self>, Error>> + Send
+        where Self: Sized + DeserializeOwned {
+        async move {
+            let request = Self::request::<()>(Method::GET, None, params, config, None)?;
+            Ok(request.send_async(client).await?.json::<Vec<Self>>().await?)
+        }
+    }
+
+    fn get_async(client: &Client, config: &Config, id: &str, params: Option<EndpointParameters>) -> impl Future<Output = Result<Self, Error>> + Send
+        where Self: Sized + DeserializeOwned {
+        async move {
+            let request = Self::request::<()>(Method::GET, Some(id), params, config, None)?;
+            Ok(request.send_async(client).await?.json::<Self>().await?)
+        }
+    }
+
+    fn delete_async(client: &Client, config: &Config, id: &str, params: Option<EndpointParameters>) -> impl Future<Output = Result<(), Error>> + Send {
+        async move {
+            let request = Self::request::<()>(Method::DELETE, Some(id), params, config, None)?;
+            request.send_async(client).await?;
+            Ok(())
+        }
+    }
+}
+
+impl<T: EndPoint> AsyncEndPoint for T {}
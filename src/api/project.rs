@@ -2,11 +2,11 @@ use std::fmt;
 use crate::clockify::Config; 
 use crate::api::{
     EndPoint,
+    Id,
     task::Task,
     common::{Rate, Membership},
 }; 
 use crate::error::Error;
-use crate::ui::components::Id;
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -76,7 +76,7 @@ impl fmt::Display for Project {
 
 impl Id for Project {
     fn id(&self) -> String {
-        return self.id.as_ref().unwrap().clone(); 
+        self.id.as_ref().unwrap().clone()
     }
 }
 
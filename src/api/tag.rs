@@ -1,8 +1,7 @@
 use std::fmt;
 use crate::{clockify::Config, error::Error}; 
-use crate::api::EndPoint; 
+use crate::api::{EndPoint, Id}; 
 use serde::{Serialize, Deserialize};
-use crate::ui::components::Id;
 
 // Name is the only required field to create a tag.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,7 +32,7 @@ impl fmt::Display for Tag {
 
 impl Id for Tag {
     fn id(&self) -> String {
-        return self.id.as_ref().unwrap().clone(); 
+        self.id.as_ref().unwrap().clone()
     }
 }
 
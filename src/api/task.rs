@@ -4,8 +4,9 @@ use crate::{
     clockify::Config, 
     api::{
         EndPoint, 
+        Id, 
         common::Rate
-    }, ui::components::Id, error::Error
+    }, error::Error
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -2,12 +2,24 @@ use std::fmt;
 use crate::{
     clockify::Config, 
     api::{
+        ApiRequest,
+        check_status,
+        check_status_async,
         EndPoint,
+        Id,
+        EndpointParameters,
+        ParameterValue,
+        nonblocking::AsyncEndPoint,
+        project::Project,
+        task::Task,
     }, 
     error::Error, 
+    format::Formatter, 
 };
+use chrono::prelude::*;
+use reqwest::{Method, StatusCode, blocking::Client};
 use serde::{Serialize, Deserialize};
-use crate::ui::components::Id;
+use serde_json::json;
 
 // On list or get for TimeEntry, the start and end will show up in
 // TimeInterval.start and TimeInterval.end, not TimeEntry.start and TimeEntry.end
@@ -26,7 +38,12 @@ pub struct TimeEntry {
     pub time_interval: Option<TimeInterval>, 
     pub user_id: Option<String>, 
     pub workspace_id: Option<String>,
-    pub custom_field_values: Option<Vec<CustomFieldValue>> 
+    pub custom_field_values: Option<Vec<CustomFieldValue>>, 
+    // Only present when listed with hydrated=true
+    #[serde(default, skip_serializing)]
+    pub project: Option<Project>, 
+    #[serde(default, skip_serializing)]
+    pub task: Option<Task>, 
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -47,6 +64,164 @@ pub struct CustomFieldValue {
     pub r#type: String
 }
 
+// Filters for listing time entries. Dates are local calendar days and both
+// ends of the range are inclusive.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeEntryFilter {
+    pub start: Option<NaiveDate>, 
+    pub end: Option<NaiveDate>, 
+    pub project_id: Option<String>, 
+    pub task_id: Option<String>, 
+    pub tag_id: Option<String>, 
+    pub billable: Option<bool>, 
+    pub running_only: bool, 
+}
+
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s).ok().map(|d| d.with_timezone(&Utc))
+}
+
+impl TimeEntryFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == TimeEntryFilter::default()
+    }
+
+    // Days start at midnight in the zone of the formatter
+    fn range(&self, formatter: &Formatter) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        (
+            self.start.and_then(|d| formatter.start_of_day(d)), 
+            self.end.and_then(|d| d.succ_opt()).and_then(|d| formatter.start_of_day(d))
+        )
+    }
+
+    // Query parameters supported by the time entry endpoint. Billable has no
+    // parameter and is only applied by matches().
+    pub fn to_params(&self, formatter: &Formatter) -> EndpointParameters {
+        let mut params = EndpointParameters::new();
+        let (start, end) = self.range(formatter);
+        if let Some(s) = start {
+            params.insert("start".to_owned(), ParameterValue::from(s));
+        }
+        if let Some(e) = end {
+            params.insert("end".to_owned(), ParameterValue::from(e));
+        }
+        if let Some(project_id) = &self.project_id {
+            params.insert("project".to_owned(), ParameterValue::from(project_id.clone()));
+        }
+        if let Some(task_id) = &self.task_id {
+            params.insert("task".to_owned(), ParameterValue::from(task_id.clone()));
+        }
+        if let Some(tag_id) = &self.tag_id {
+            params.insert("tags".to_owned(), ParameterValue::from(tag_id.clone()));
+        }
+        if self.running_only {
+            params.insert("in-progress".to_owned(), ParameterValue::from(true));
+        }
+        params
+    }
+
+    pub fn matches(&self, entry: &TimeEntry, formatter: &Formatter) -> bool {
+        let (start, end) = self.range(formatter);
+        let entry_start = entry.start_time();
+        if let Some(s) = start {
+            if entry_start.is_none_or(|e| e < s) {
+                return false;
+            }
+        }
+        if let Some(e) = end {
+            if entry_start.is_none_or(|s| s >= e) {
+                return false;
+            }
+        }
+        if self.project_id.is_some() && entry.project_id != self.project_id {
+            return false;
+        }
+        if self.task_id.is_some() && entry.task_id != self.task_id {
+            return false;
+        }
+        if let Some(tag_id) = &self.tag_id {
+            if !entry.tag_ids.as_ref().is_some_and(|tags| tags.contains(tag_id)) {
+                return false;
+            }
+        }
+        if let Some(billable) = self.billable {
+            if entry.billable.unwrap_or(false) != billable {
+                return false;
+            }
+        }
+        if self.running_only && !entry.is_running() {
+            return false;
+        }
+        true
+    }
+}
+
+impl TimeEntry {
+    // Stop the running timer of the user. Returns the stopped entry, or None
+    // when no timer was running.
+    pub fn stop(client: &Client, config: &Config, end: DateTime<Utc>) -> Result<Option<TimeEntry>, Error> {
+        let response = Self::stop_request(config, end)?.blocking(client).send()?;
+        // Clockify also answers 404 for an unknown workspace or user, whose
+        // in-progress query then fails too
+        if response.status() == StatusCode::NOT_FOUND && Self::list(client, config, Some(Self::in_progress()))?.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(check_status(response)?.json::<TimeEntry>()?))
+    }
+
+    pub async fn stop_async(client: &reqwest::Client, config: &Config, end: DateTime<Utc>) -> Result<Option<TimeEntry>, Error> {
+        let response = Self::stop_request(config, end)?.nonblocking(client).send().await?;
+        if response.status() == StatusCode::NOT_FOUND && Self::list_async(client, config, Some(Self::in_progress())).await?.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(check_status_async(response).await?.json::<TimeEntry>().await?))
+    }
+
+    fn stop_request(config: &Config, end: DateTime<Utc>) -> Result<ApiRequest, Error> {
+        Self::request(Method::PATCH, None, None, config, Some(&json!({ "end": format_timestamp(end) })))
+    }
+
+    fn in_progress() -> EndpointParameters {
+        EndpointParameters::new().with("in-progress", true)
+    }
+
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.time_interval.as_ref()?.start.as_deref().and_then(parse_timestamp)
+    }
+
+    pub fn end_time(&self) -> Option<DateTime<Utc>> {
+        self.time_interval.as_ref()?.end.as_deref().and_then(parse_timestamp)
+    }
+
+    // Body for an update, which takes start and end at the top level
+    pub fn for_update(&self) -> TimeEntry {
+        TimeEntry {
+            start: self.start.clone().or_else(|| self.time_interval.as_ref()?.start.clone()), 
+            end: self.end.clone().or_else(|| self.time_interval.as_ref()?.end.clone()), 
+            billable: self.billable, 
+            description: self.description.clone(), 
+            project_id: self.project_id.clone(), 
+            task_id: self.task_id.clone(), 
+            tag_ids: self.tag_ids.clone(), 
+            ..Default::default()
+        }
+    }
+
+    // Elapsed time, counting up to now while the entry is running
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        let end = self.end_time().unwrap_or_else(Utc::now);
+        Some(end - self.start_time()?)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.time_interval.as_ref().is_some_and(|t| t.end.is_none())
+    }
+}
+
 impl fmt::Display for TimeEntry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(d) = &self.description {
@@ -67,4 +242,36 @@ impl EndPoint for TimeEntry {
     fn endpoint(config: &Config) -> Result<String, Error> {
         Ok(format!("/workspaces/{}/user/{}/time-entries", config.workspace_id.as_ref().ok_or(Error::MissingWorkspace)?.clone(), config.user_id.as_ref().ok_or(Error::MissingUser)?))
     }
+
+    fn item_endpoint(config: &Config) -> Result<String, Error> {
+        Ok(format!("/workspaces/{}/time-entries", config.workspace_id.as_ref().ok_or(Error::MissingWorkspace)?.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Zone;
+
+    fn entry_at(start: DateTime<Utc>) -> TimeEntry {
+        TimeEntry {
+            time_interval: Some(TimeInterval { start: Some(format_timestamp(start)), ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn days_in_the_formatter_zone() {
+        let formatter = Formatter { zone: Zone::Named(chrono_tz::Asia::Tokyo), ..Formatter::default() };
+        let day = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        let filter = TimeEntryFilter { start: Some(day), end: Some(day), ..Default::default() };
+        // 2024-03-13 in Tokyo (UTC+9)
+        assert_eq!(
+            filter.range(&formatter),
+            (Some(Utc.with_ymd_and_hms(2024, 3, 12, 15, 0, 0).unwrap()), Some(Utc.with_ymd_and_hms(2024, 3, 13, 15, 0, 0).unwrap()))
+        );
+        assert!(filter.matches(&entry_at(Utc.with_ymd_and_hms(2024, 3, 12, 16, 0, 0).unwrap()), &formatter));
+        assert!(!filter.matches(&entry_at(Utc.with_ymd_and_hms(2024, 3, 13, 16, 0, 0).unwrap()), &formatter));
+        assert_eq!(filter.to_params(&formatter).get("start"), Some(&ParameterValue::from(Utc.with_ymd_and_hms(2024, 3, 12, 15, 0, 0).unwrap())));
+    }
 }
@@ -2,11 +2,11 @@ use std::fmt;
 use crate::clockify::Config; 
 use crate::api::{
     EndPoint, 
+    Id, 
     common::{Rate, Membership}
 };
 use crate::error::Error; 
 use serde::{Serialize, Deserialize};
-use crate::ui::components::Id;
 
 // Name is the only required field to create a tag.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,25 +1,34 @@
 use crate::{
     api::{
         EndPoint,
+        check_status,
+        EndpointParameters,
+        ParameterValue,
         tag::Tag, 
         project::Project, 
-        time_entry::TimeEntry, 
+        time_entry::{TimeEntry, TimeEntryFilter, format_timestamp}, 
+        user::User, 
         workspace::Workspace, task::Task,
     },
     ui::{
-        components::{StatefulList, InputBox, Id},
-        Screen
+        components::{StatefulList, InputBox, Id, FilterForm},
+        grid::{GridRow, WeeklyGrid},
+        ScreenId
     }, 
-    error::Error
+    error::Error, 
+    local_config::LocalConfig, 
+    state::{NameCache, State}, 
+    time_parser::TimeExpr, 
+    format::Formatter, 
+    git, 
 };
 
-use chrono::prelude::*;
-use crossterm::event::{KeyEvent, KeyModifiers, KeyCode};
+use chrono::{prelude::*, Duration};
 use reqwest::blocking::Client;
 use serde::{Serialize, Deserialize};
-use std::fmt; 
+use std::{collections::BTreeMap, fmt, path::PathBuf, process::Command, time::Instant};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
     Navigation, 
     Edit, 
@@ -46,56 +55,389 @@ impl fmt::Display for AppMode {
 pub struct App<'a> {
     pub title: &'a str, 
     pub should_quit: bool,
+    // Every profile as loaded from the config file
+    pub config_file: ConfigFile, 
+    // Name of the active profile, its settings are in config
+    pub profile: String, 
+    // Profile the app was started with, the only one CLOCKIFY_API_KEY is
+    // used for
+    pub startup_profile: String, 
     pub config: Config,
-    pub current_screen: Screen, 
+    pub current_screen: ScreenId, 
+    // Screens left with navigate(), and those left with back()
+    pub history: Vec<ScreenId>, 
+    pub forward: Vec<ScreenId>, 
+    // Entry shown on the Home screen
     pub current_entry_id: Option<String>, 
+    // That entry as last loaded, so that rendering needs no request
+    pub current_entry: Option<TimeEntry>, 
+    // Running timer as last seen by this app
+    pub running_entry_id: Option<String>, 
+    pub last_poll: Option<Instant>, 
     pub current_mode: AppMode, 
     pub workspaces: StatefulList<Workspace>,
     pub projects: StatefulList<Project>,
     pub tasks: StatefulList<Task>, 
     pub tags: StatefulList<Tag>, 
     pub description: InputBox, 
+    // Start time or range typed on the Time Input screen
+    pub time_input: InputBox, 
     pub time_entries: StatefulList<TimeEntry>,
-    pub error: Option<Error>, 
+    pub time_entry_filter: TimeEntryFilter, 
+    pub filter_form: FilterForm, 
+    pub timesheet_view: bool, 
+    pub grid: WeeklyGrid, 
+    pub profiles: StatefulList<Profile>, 
+    // .clockify.toml of the current directory
+    pub local_config: Option<LocalConfig>, 
+    // Git branch checked out in the current directory
+    pub git_branch: Option<String>, 
+    // First run: the API key is entered in the app and the config is saved
+    // once a workspace is picked
+    pub onboarding: bool, 
+    pub api_key_input: InputBox, 
+    pub user: Option<User>, 
+    // Renders timestamps following the user's settings
+    pub formatter: Formatter, 
+    // Status line shown below the title
+    pub message: Option<String>, 
 }
 
 impl<'a> fmt::Display for App<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} ({} Mode)", self.title, self.current_mode)
+        write!(f, "{} [{}] ({} Mode)", self.title, self.profile, self.current_mode)
 
     }
 }
 
 impl<'a> App<'a> {
-    pub fn new(title: &'a str) -> Result<App<'a>, Error> {
-        Ok(App {
+    pub fn new(title: &'a str, config_file: ConfigFile, profile: String) -> App<'a> {
+        let config = config_file.profile(&profile);
+        let profiles = config_file.profiles
+            .iter()
+            .map(|(name, config)| Profile { name: name.clone(), config: config.clone() })
+            .collect();
+        let mut profiles = StatefulList::with_items(profiles, String::from("Select a profile: "), false);
+        profiles.selected = vec![profile.clone()];
+        App {
             title, 
             should_quit: false, 
-            config: confy::load("clockify")?, 
-            current_screen: Screen::Loading, 
+            config_file, 
+            startup_profile: profile.clone(), 
+            profile, 
+            config, 
+            current_screen: ScreenId::Loading, 
+            history: vec![], 
+            forward: vec![], 
             current_entry_id: None, 
+            current_entry: None, 
+            running_entry_id: None, 
+            last_poll: None, 
             current_mode: AppMode::Navigation, 
             workspaces: StatefulList::with_items(vec![], String::from("Select a workspace: "), false), 
             tasks: StatefulList::with_items(vec![], String::from("Select a task: "), false), 
             projects: StatefulList::with_items(vec![], String::from("Select a project: "), false),
             tags: StatefulList::with_items(vec![], String::from("Select a tag: "), true), 
             description: InputBox::from("Edit the time entry description: "), 
+            time_input: InputBox::from("Start at (e.g. 9am, -20m, yesterday 14:00-15:30, last friday 2h): "), 
             time_entries: StatefulList::with_items(vec![], String::from("Select a time entry: "), false), 
-            error: None,
-        })
+            time_entry_filter: TimeEntryFilter::default(), 
+            filter_form: FilterForm::default(), 
+            timesheet_view: false, 
+            grid: WeeklyGrid::default(), 
+            profiles, 
+            local_config: None, 
+            git_branch: None, 
+            onboarding: false, 
+            api_key_input: InputBox::from("Paste your API key (Profile Settings > API in Clockify) and press Enter: "), 
+            user: None, 
+            formatter: Formatter::default(), 
+            message: None, 
+        }
+    }
+
+    // Write the active profile back and save every profile
+    pub fn store_config(&mut self) -> Result<(), Error> {
+        self.save_profile();
+        confy::store("clockify", &self.config_file)?;
+        Ok(())
+    }
+
+    fn save_profile(&mut self) {
+        let stored = self.config_file.profile(&self.profile);
+        let mut config = self.config.to_stored(&stored);
+        // The project of a .clockify.toml only applies to this session
+        if self.local_config.as_ref().is_some_and(|l| l.project.is_some()) {
+            config.project_id = stored.project_id;
+        }
+        self.config_file.profiles.insert(self.profile.clone(), config);
+    }
+
+    // Start over with the state of another profile
+    pub fn switch_profile(&mut self, name: &str) {
+        if name == self.profile {
+            self.navigate(ScreenId::Home);
+            return;
+        }
+        let mut config = self.config_file.profile(name);
+        let resolved = if name == self.startup_profile {
+            config.resolve_api_key()
+        } else {
+            config.resolve_profile_api_key()
+        };
+        if let Err(e) = resolved {
+            self.message = Some(format!("Could not get the API key of profile {}: {}", name, e));
+            return;
+        }
+        self.save_profile();
+        let mut app = App::new(self.title, self.config_file.clone(), name.to_owned());
+        if config.api_key.is_none() {
+            app.onboarding = true;
+            app.current_screen = ScreenId::Onboarding;
+            app.current_mode = AppMode::Edit;
+        }
+        app.config = config;
+        app.startup_profile = self.startup_profile.clone();
+        app.local_config = self.local_config.clone();
+        app.git_branch = self.git_branch.clone();
+        app.message = Some(format!("Switched to profile {}", name));
+        *self = app;
+    }
+
+    // The user the API key belongs to, fetched once
+    pub fn current_user(&mut self, client: &Client) -> Result<User, Error> {
+        if let Some(user) = &self.user {
+            return Ok(user.clone());
+        }
+        let response = client.get(format!("{}{}", self.config.base_url, "/user"))
+            .header("X-API-KEY", self.config.api_key.as_ref().ok_or(Error::MissingApiKey)?.clone())
+            .send()?;
+        let user = check_status(response)?.json::<User>()?;
+        self.config.user_id = user.id.clone();
+        self.formatter = Formatter::new(user.settings.as_ref(), self.config.local_time_zone);
+        self.user = Some(user.clone());
+        Ok(user)
     }
 
     pub fn get_current_entry(&mut self, client: &Client) -> Result<Option<TimeEntry>, Error> {
         if let Some(time_entry_id) = self.current_entry_id.clone() {
-            return Ok(Some(client.get(format!("{}/workspaces/{}/time-entries/{}", self.config.base_url, self.config.workspace_id.as_ref().ok_or(Error::MissingWorkspace)?.clone(), time_entry_id))
-                .header("X-API-KEY", self.config.api_key.as_ref().ok_or(Error::MissingApiKey)?.clone())
-                .send()?
-                .json::<TimeEntry>()?));
+            Ok(Some(TimeEntry::get(client, &self.config, &time_entry_id, None)?))
         } else {
             Ok(None)
         }
     }
 
+    // Running entry of the user, if any
+    pub fn running_entry(&mut self, client: &Client) -> Result<Option<TimeEntry>, Error> {
+        let mut params = EndpointParameters::new();
+        params.insert("in-progress".to_owned(), ParameterValue::from(true));
+        params.insert("hydrated".to_owned(), ParameterValue::from(true));
+        let time_entries = TimeEntry::list(client, &self.config, Some(params))?;
+        Ok(time_entries.into_iter().next())
+    }
+
+    // Save the running timer and today's total for `clockify status`
+    pub fn update_state(&mut self, client: &Client) {
+        if let Err(e) = self.write_state(client) {
+            self.message = Some(format!("Could not save the timer state: {}", e));
+        }
+    }
+
+    fn write_state(&mut self, client: &Client) -> Result<(), Error> {
+        // Days follow the zone of the user's settings
+        self.current_user(client)?;
+        let today = self.formatter.today();
+        let filter = TimeEntryFilter { start: Some(today), end: Some(today), ..Default::default() };
+        let mut params = filter.to_params(&self.formatter);
+        params.insert("hydrated".to_owned(), ParameterValue::from(true));
+        let time_entries = TimeEntry::list(client, &self.config, Some(params))?;
+        // A timer started before today is not in today's entries
+        let running = match time_entries.iter().find(|entry| entry.is_running()) {
+            Some(entry) => Some(entry.clone()), 
+            None if self.running_entry_id.is_some() => self.running_entry(client)?, 
+            None => None, 
+        };
+        State::new(&time_entries, running.as_ref(), &self.formatter).store(self.config.cache_dir.as_deref(), &self.profile)
+    }
+
+    // Save the names of the loaded lists for shell completion
+    pub fn update_name_cache(&mut self) {
+        let names = NameCache {
+            projects: self.projects.items.iter().map(|project| project.name.clone()).collect(), 
+            tasks: self.tasks.items.iter().map(|task| task.name.clone()).collect(), 
+            tags: self.tags.items.iter().map(|tag| tag.name.clone()).collect(), 
+        };
+        if let Err(e) = names.store(self.config.cache_dir.as_deref(), &self.profile) {
+            self.message = Some(format!("Could not save the completion cache: {}", e));
+        }
+    }
+
+    // Check for timers started or stopped elsewhere (web app, phone, ...)
+    // every poll_interval seconds.
+    pub fn on_tick(&mut self, client: &Client) {
+        if self.config.poll_interval == 0 || self.config.workspace_id.is_none() || self.config.user_id.is_none() {
+            return;
+        }
+        let first_poll = match self.last_poll {
+            Some(t) if t.elapsed().as_secs() < self.config.poll_interval => return, 
+            Some(_) => false, 
+            None => true, 
+        };
+        self.last_poll = Some(Instant::now());
+        match self.running_entry(client) {
+            Ok(running) => {
+                let changed = running.as_ref().and_then(|e| e.id.clone()) != self.running_entry_id;
+                self.sync_running_entry(running, !first_poll);
+                if first_poll || changed {
+                    self.update_state(client);
+                }
+            }, 
+            Err(e) => self.message = Some(format!("Could not check for a running timer: {}", e)), 
+        }
+    }
+
+    fn sync_running_entry(&mut self, running: Option<TimeEntry>, notify: bool) {
+        let running_id = running.as_ref().and_then(|e| e.id.clone());
+        if running_id == self.running_entry_id {
+            return;
+        }
+        match &running {
+            Some(time_entry) => {
+                self.current_entry_id = running_id.clone();
+                self.select_entry(time_entry);
+                if notify {
+                    self.message = Some(format!("Timer started elsewhere: {}", time_entry));
+                }
+            }, 
+            None => {
+                // Keep showing the stopped entry on the Home screen
+                if notify {
+                    self.message = Some(String::from("Timer stopped elsewhere"));
+                }
+            }
+        }
+        self.running_entry_id = running_id;
+    }
+
+    // Use an API key if the /user endpoint accepts it
+    pub fn validate_api_key(&mut self, client: &Client, api_key: &str) -> Result<User, Error> {
+        self.config.api_key = Some(api_key.trim().to_owned());
+        self.config.api_key_from_secret = false;
+        self.config.user_id = None;
+        self.user = None;
+        let user = self.current_user(client);
+        if user.is_err() {
+            self.config.api_key = None;
+        }
+        user
+    }
+
+    // Select a project, task and tags by name (or id), loading the lists as
+    // needed, and set the description.
+    pub fn apply_selections(&mut self, client: &Client, project: Option<&str>, task: Option<&str>, tags: &[String], description: Option<String>) -> Result<(), Error> {
+        if let Some(name) = project {
+            if self.projects.items.is_empty() {
+                self.projects.items = Project::list(client, &self.config, None)?;
+            }
+            let project_id = find_by_name(&self.projects.items, name)
+                .ok_or_else(|| Error::UnknownName(name.to_owned()))?
+                .id();
+            self.projects.selected = vec![project_id.clone()];
+            // Tasks are listed per project
+            if self.config.project_id.as_ref() != Some(&project_id) {
+                self.config.project_id = Some(project_id);
+                self.tasks.items = vec![];
+            }
+        }
+        if let Some(name) = task {
+            if self.config.project_id.is_none() {
+                return Err(Error::MissingProject);
+            }
+            if self.tasks.items.is_empty() {
+                self.tasks.items = Task::list(client, &self.config, None)?;
+            }
+            let task_id = find_by_name(&self.tasks.items, name)
+                .ok_or_else(|| Error::UnknownName(name.to_owned()))?
+                .id();
+            self.tasks.selected = vec![task_id];
+        }
+        if !tags.is_empty() {
+            if self.tags.items.is_empty() {
+                self.tags.items = Tag::list(client, &self.config, None)?;
+            }
+            self.tags.selected = tags
+                .iter()
+                .map(|name| {
+                    find_by_name(&self.tags.items, name)
+                        .map(|tag| tag.id())
+                        .ok_or_else(|| Error::UnknownName(name.clone()))
+                })
+                .collect::<Result<Vec<String>, Error>>()?;
+        }
+        if let Some(description) = description {
+            self.description.text = description;
+        }
+        Ok(())
+    }
+
+    // Preselect the defaults of a .clockify.toml
+    pub fn apply_local_config(&mut self, client: &Client) -> Result<(), Error> {
+        if let Some(local_config) = self.local_config.clone() {
+            self.apply_selections(
+                client, 
+                local_config.project.as_deref(), 
+                local_config.task.as_deref(), 
+                &local_config.tags, 
+                local_config.description(self.git_branch.as_deref()), 
+            )?;
+        }
+        Ok(())
+    }
+
+    // Fill in the description from the git branch and preselect the task
+    // named after its issue key, if enabled and nothing else was chosen
+    pub fn apply_git_branch(&mut self, client: &Client) -> Result<(), Error> {
+        let branch = match &self.git_branch {
+            Some(branch) => branch.clone(), 
+            None => return Ok(()), 
+        };
+        if self.config.branch_description && self.description.text.is_empty() {
+            self.description.text = git::branch_description(&branch);
+        }
+        // Only tasks of the selected project are candidates
+        let project_id = match self.projects.selected.first() {
+            Some(project_id) => project_id.clone(), 
+            None => return Ok(()), 
+        };
+        if self.config.branch_task && self.tasks.selected.is_empty() {
+            if let Some(key) = git::issue_key(&branch) {
+                // Tasks are listed per project
+                if self.config.project_id.as_ref() != Some(&project_id) {
+                    self.config.project_id = Some(project_id);
+                    self.tasks.items = vec![];
+                }
+                if self.tasks.items.is_empty() {
+                    self.tasks.items = Task::list(client, &self.config, None)?;
+                }
+                let key = key.to_lowercase();
+                if let Some(task) = self.tasks.items.iter().find(|task| task.name.to_lowercase().contains(&key)) {
+                    self.tasks.selected = vec![task.id()];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Show the fields of an entry on the Home screen
+    pub fn select_entry(&mut self, time_entry: &TimeEntry) {
+        // Change project, task and tags, clearing those the entry has none of
+        self.projects.selected = time_entry.project_id.iter().cloned().collect();
+        self.tasks.selected = time_entry.task_id.iter().cloned().collect();
+        self.tags.selected = time_entry.tag_ids.clone().unwrap_or_default();
+        // Change description
+        self.description.text = time_entry.description.clone().unwrap_or_default();
+    }
+
     pub fn get_current_entry_with_selections(&mut self, client: &Client) -> Result<TimeEntry, Error> {
         let mut time_entry : TimeEntry = if let Some(t) = &self.get_current_entry(client)? {
             t.clone()
@@ -123,96 +465,258 @@ impl<'a> App<'a> {
     }
 
     pub fn start_entry(&mut self, client: &Client) -> Result<(), Error> {
+        self.start_entry_at(client, TimeExpr::At(Utc::now()))
+    }
+
+    // Start a timer at a time, or add a finished entry for a range
+    pub fn start_entry_at(&mut self, client: &Client, time: TimeExpr) -> Result<(), Error> {
         // Send POST new time entry with only start
         let mut time_entry = self.get_current_entry_with_selections(client)?;
         // Replace start and end times
         time_entry.id = None;
-        time_entry.end = None;
         time_entry.time_interval = None;
-        // Add current start time
-        time_entry.start = Some(self.current_formatted_time());
+        time_entry.start = Some(format_timestamp(time.start()));
+        time_entry.end = time.end().map(format_timestamp);
         
         // POST request to create
         let time_entry = TimeEntry::create(time_entry, client, &self.config, None)?;
-        self.current_entry_id = time_entry.id;
+        self.current_entry_id = time_entry.id.clone();
+        if time.end().is_none() {
+            self.running_entry_id = time_entry.id;
+        }
+        self.update_state(client);
         Ok(())
     }
 
-    pub fn stop_entry(&mut self, client: &Client) -> Result<(), Error> {
-        // Send PATCH with only end
-        let time_entry = TimeEntry { end: Some(self.current_formatted_time()), ..Default::default() };
-        TimeEntry::patch(time_entry, client, &self.config, None)?;
-        Ok(())
+    // Resolve a time expression such as "yesterday 14:00-15:30" in the
+    // user's time zone, once the user is loaded
+    pub fn parse_time(&self, input: &str) -> Result<TimeExpr, Error> {
+        let start_of_day = self.user
+            .as_ref()
+            .and_then(|user| user.settings.as_ref())
+            .and_then(|s| NaiveTime::parse_from_str(&s.my_start_of_day, "%H:%M").ok())
+            .unwrap_or_else(|| NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        self.formatter.parse_time(input, Utc::now(), start_of_day)
+    }
+
+    // Stop the running timer, returning the stopped entry if there was one
+    pub fn stop_entry(&mut self, client: &Client) -> Result<Option<TimeEntry>, Error> {
+        let time_entry = TimeEntry::stop(client, &self.config, Utc::now())?;
+        self.current_entry_id = None;
+        self.running_entry_id = None;
+        Ok(time_entry)
+    }
+
+    // Stop the running timer, if any, and start a new one with the same fields
+    pub fn continue_entry(&mut self, client: &Client, time_entry: &TimeEntry) -> Result<TimeEntry, Error> {
+        self.stop_entry(client)?;
+        let time_entry = TimeEntry {
+            start: Some(self.current_formatted_time()), 
+            billable: time_entry.billable, 
+            description: time_entry.description.clone(), 
+            project_id: time_entry.project_id.clone(), 
+            task_id: time_entry.task_id.clone(), 
+            tag_ids: time_entry.tag_ids.clone(), 
+            ..Default::default()
+        };
+        let time_entry = TimeEntry::create(time_entry, client, &self.config, None)?;
+        self.current_entry_id = time_entry.id.clone();
+        self.running_entry_id = time_entry.id.clone();
+        self.select_entry(&time_entry);
+        self.update_state(client);
+        Ok(time_entry)
     }
 
     pub fn update_entry(&mut self, client: &Client) -> Result<(), Error> {
-        // Send PATCH with only end
         let time_entry = self.get_current_entry_with_selections(client)?;
-        // POST request to create
-        TimeEntry::update(time_entry, client, &self.config, None)?;
+        let time_entry_id = time_entry.id.clone().ok_or(Error::MissingTimeEntry)?;
+        // PUT request to update
+        TimeEntry::update(time_entry.for_update(), client, &self.config, &time_entry_id, None)?;
+        self.update_state(client);
         Ok(())
     }
 
-    pub fn key_event(&mut self, key: KeyEvent, client: &Client) -> Result<(), Error> {
-        match key.modifiers {
-            KeyModifiers::CONTROL => {
-                if let KeyCode::Char(c) = key.code {
-                    if c== 'q' {
-                        self.should_quit = true;
-                    }
-               }
-            },
-            KeyModifiers::NONE => {
-                match self.current_mode {
-                    AppMode::Navigation => {
-                        if let KeyCode::Char(c) = key.code {
-                            match c {
-                                'w' => { self.current_screen = Screen::WorkspaceSelection }, 
-                                't' => { self.current_screen = Screen::TimeEntrySelection },
-                                'p' => { self.current_screen = Screen::ProjectSelection },
-                                'g' => { self.current_screen = Screen::TagSelection },
-                                'y' => { 
-                                    self.current_screen = Screen::TaskSelection;
-                                    // If selected project has changed, clear tasks
-                                    if let Some(config_project_id) = &self.config.project_id {
-                                        if let Some(selected_project) = self.projects.get_selected_item() {
-                                            if config_project_id.clone() != selected_project.clone().id() {
-                                                self.tasks.items = vec![];
-                                            }
-                                        }
-                                    }
-                                },
-                                'd' => { self.current_screen = Screen::DescriptionEdit }, 
-                                'h' => { self.current_screen = Screen::Home },
-                                'i' => { self.current_mode = AppMode::Edit }, 
-                                '/' => { self.current_mode = AppMode::Search },
-                                'u' => { self.update_entry(client)?; },
-                                's' => { self.start_entry(client)?; }, 
-                                'e' => { self.stop_entry(client)?; },
-                                _ => {}
-                            }
-                        }
-                    }, 
-                    _ => {
-                        if key.code == KeyCode::Esc {
-                            self.current_mode = AppMode::Navigation;
-                        }
-                    }
-                }
+    // Make the time tracked for a project/task on a day add up to total by
+    // creating, adjusting or deleting entries.
+    pub fn set_duration_for_day(&mut self, client: &Client, row: &GridRow, day: NaiveDate, total: Duration) -> Result<(), Error> {
+        let entries = self.grid.cell_entries(row, day, &self.formatter);
+        if entries.iter().any(|entry| entry.is_running()) {
+            return Err(Error::RunningTimeEntry);
+        }
+        let current = self.grid.cell_total(row, day, &self.formatter);
+        let mut delta = total - current;
+        if delta == Duration::zero() {
+            return Ok(());
+        }
+
+        // Nothing tracked yet: add an entry after the last one of the day
+        if entries.is_empty() {
+            let start_of_day = self.current_user(client)?
+                .settings
+                .and_then(|s| NaiveTime::parse_from_str(&s.my_start_of_day, "%H:%M").ok())
+                .unwrap_or_else(|| NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+            let day_start = self.formatter.to_utc(day.and_time(start_of_day)).ok_or(Error::MissingData)?;
+            let start = match self.grid.day_end(day, &self.formatter) {
+                Some(end) if end > day_start => end, 
+                _ => day_start, 
+            };
+            let time_entry = TimeEntry {
+                start: Some(format_timestamp(start)), 
+                end: Some(format_timestamp(start + total)), 
+                project_id: row.project_id.clone(), 
+                task_id: row.task_id.clone(), 
+                ..Default::default()
+            };
+            TimeEntry::create(time_entry, client, &self.config, None)?;
+            return Ok(());
+        }
+
+        // Adjust the latest entry, removing later ones while shrinking
+        for entry in entries.iter().rev() {
+            let id = entry.id.clone().ok_or(Error::MissingTimeEntry)?;
+            let duration = entry.duration().ok_or(Error::MissingData)?;
+            if duration + delta > Duration::zero() {
+                let mut update = entry.for_update();
+                update.end = Some(format_timestamp(entry.start_time().ok_or(Error::MissingData)? + duration + delta));
+                TimeEntry::update(update, client, &self.config, &id, None)?;
+                break;
+            }
+            TimeEntry::delete(client, &self.config, &id, None)?;
+            delta += duration;
+            if delta == Duration::zero() {
+                break;
             }
-            _ => {}
         }
         Ok(())
     }
+
+    // Show another screen, remembering the current one for back()
+    pub fn navigate(&mut self, screen: ScreenId) {
+        if screen == self.current_screen {
+            return;
+        }
+        // A screen already in the history is returned to as if by back(), so
+        // the history never holds a screen twice
+        if let Some(i) = self.history.iter().position(|s| *s == screen) {
+            self.history.truncate(i);
+        } else if !matches!(self.current_screen, ScreenId::Loading | ScreenId::Onboarding) {
+            // Loading and signing in are never returned to
+            self.history.push(self.current_screen);
+        }
+        self.forward.clear();
+        self.current_screen = screen;
+    }
+
+    // Show another screen in place of the current one, which back() then
+    // skips, e.g. when the current one cannot be used yet
+    pub fn redirect(&mut self, screen: ScreenId) {
+        self.current_screen = screen;
+    }
+
+    // Return to the previous screen, if any
+    pub fn back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(screen) => {
+                self.forward.push(self.current_screen);
+                self.current_screen = screen;
+                true
+            }, 
+            None => false, 
+        }
+    }
+
+    // Undo back()
+    pub fn forward(&mut self) -> bool {
+        match self.forward.pop() {
+            Some(screen) => {
+                self.history.push(self.current_screen);
+                self.current_screen = screen;
+                true
+            }, 
+            None => false, 
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Config {
     pub base_url: String,
     pub api_key: Option<String>, 
+    // Command printing the API key, e.g. "pass show clockify"
+    pub api_key_command: Option<String>, 
+    // Set to false to never write the API key to the config file
+    pub store_api_key: bool, 
+    // The API key came from CLOCKIFY_API_KEY or api_key_command
+    #[serde(skip)]
+    pub api_key_from_secret: bool, 
     pub workspace_id: Option<String>,
     pub project_id: Option<String>, 
-    pub user_id: Option<String>
+    pub user_id: Option<String>, 
+    pub mouse_capture: bool, 
+    // Seconds between checks for a timer started elsewhere, 0 to disable
+    pub poll_interval: u64, 
+    // Show and type times in the system zone instead of the Clockify one
+    pub local_time_zone: bool, 
+    // Describe new entries after the git branch
+    pub branch_description: bool, 
+    // Preselect the task whose name contains the issue key of the git branch
+    pub branch_task: bool, 
+    // Directory of the state and completion files, defaults to the user's
+    // cache directory
+    pub cache_dir: Option<PathBuf>, 
+}
+
+impl Config {
+    // Find the API key of the profile chosen at startup when none was passed
+    // on the command line: the CLOCKIFY_API_KEY environment variable, then
+    // the profile's own key.
+    pub fn resolve_api_key(&mut self) -> Result<(), Error> {
+        if let Ok(api_key) = std::env::var("CLOCKIFY_API_KEY") {
+            if !api_key.is_empty() {
+                self.api_key = Some(api_key);
+                self.api_key_from_secret = true;
+                return Ok(());
+            }
+        }
+        self.resolve_profile_api_key()
+    }
+
+    // The key stored in the config file, else the output of api_key_command
+    pub fn resolve_profile_api_key(&mut self) -> Result<(), Error> {
+        if self.api_key.is_some() {
+            return Ok(());
+        }
+        if let Some(command) = &self.api_key_command {
+            let output = if cfg!(windows) {
+                Command::new("cmd").args(["/C", command]).output()?
+            } else {
+                Command::new("sh").args(["-c", command]).output()?
+            };
+            if !output.status.success() {
+                return Err(Error::ApiKeyCommand(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
+            }
+            let api_key = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+            if api_key.is_empty() {
+                return Err(Error::ApiKeyCommand(format!("{} printed no API key", command)));
+            }
+            self.api_key = Some(api_key);
+            self.api_key_from_secret = true;
+        }
+        Ok(())
+    }
+
+    // The config as written to the config file
+    fn to_stored(&self, stored: &Config) -> Config {
+        let mut config = self.clone();
+        if !self.store_api_key {
+            config.api_key = None;
+        } else if self.api_key_from_secret {
+            // Keep whatever key was in the file before
+            config.api_key = stored.api_key.clone();
+        }
+        config
+    }
 }
 
 impl Default for Config {
@@ -220,9 +724,73 @@ impl Default for Config {
         Config {
             base_url: String::from("https://api.clockify.me/api/v1"), 
             api_key: None, 
+            api_key_command: None, 
+            store_api_key: true, 
+            api_key_from_secret: false, 
             workspace_id: None,
             project_id: None,
-            user_id: None
+            user_id: None, 
+            mouse_capture: true, 
+            poll_interval: 30, 
+            local_time_zone: false, 
+            branch_description: false, 
+            branch_task: false, 
+            cache_dir: None, 
         }
     }
 }
+
+// Item with the given id, or else named name (ignoring case)
+pub fn find_by_name<'b, T: fmt::Display + Id>(items: &'b [T], name: &str) -> Option<&'b T> {
+    items.iter()
+        .find(|item| item.id() == name)
+        .or_else(|| items.iter().find(|item| item.to_string().eq_ignore_ascii_case(name)))
+}
+
+// Config file with one Config per named profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ConfigFile {
+    // Profile used when --profile is not given
+    pub default_profile: Option<String>, 
+    pub profiles: BTreeMap<String, Config>, 
+}
+
+impl ConfigFile {
+    pub fn load() -> Result<ConfigFile, Error> {
+        let mut config_file: ConfigFile = confy::load("clockify")?;
+        // Older versions stored a single Config at the top level
+        if config_file.profiles.is_empty() {
+            let config: Config = confy::load("clockify")?;
+            config_file.profiles.insert(String::from("default"), config);
+        }
+        Ok(config_file)
+    }
+
+    // Settings of a profile, a new profile starts from the defaults
+    pub fn profile(&self, name: &str) -> Config {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String, 
+    pub config: Config, 
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.config.api_key.is_some() || self.config.api_key_command.is_some() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} (no API key)", self.name)
+        }
+    }
+}
+
+impl Id for Profile {
+    fn id(&self) -> String {
+        self.name.clone()
+    }
+}
@@ -1,16 +1,60 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub enum Error {
     Confy(confy::ConfyError), 
     Io(std::io::Error),
     MissingWorkspace,
     MissingTimeEntry,
+    RunningTimeEntry,
     MissingProject,
     MissingUser,
     MissingData, 
     MissingApiKey, 
+    ApiKeyCommand(String), 
+    // No project, task or tag with this name
+    UnknownName(String), 
+    InvalidTime(String), 
+    Toml(toml::de::Error), 
+    Json(serde_json::Error), 
     Api(crate::api::EndpointError),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Confy(e) => write!(f, "Could not read or write the config file: {}", e), 
+            Error::Io(e) => write!(f, "{}", e), 
+            Error::MissingWorkspace => write!(f, "No workspace selected"), 
+            Error::MissingTimeEntry => write!(f, "No time entry selected"), 
+            Error::RunningTimeEntry => write!(f, "A timer is already running"), 
+            Error::MissingProject => write!(f, "No project selected"), 
+            Error::MissingUser => write!(f, "The user is not known yet"), 
+            Error::MissingData => write!(f, "Missing data"), 
+            Error::MissingApiKey => write!(f, "No API key, set one with --api-key or CLOCKIFY_API_KEY"), 
+            Error::ApiKeyCommand(e) => write!(f, "api_key_command failed: {}", e), 
+            Error::UnknownName(name) => write!(f, "Nothing named {}", name), 
+            Error::InvalidTime(e) => write!(f, "Invalid time {}", e), 
+            Error::Toml(e) => write!(f, "Invalid {}: {}", crate::local_config::FILE_NAME, e), 
+            Error::Json(e) => write!(f, "{}", e), 
+            Error::Api(e) => write!(f, "{}", e), 
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Confy(e) => Some(e), 
+            Error::Io(e) => Some(e), 
+            Error::Toml(e) => Some(e), 
+            Error::Json(e) => Some(e), 
+            Error::Api(e) => Some(e), 
+            _ => None, 
+        }
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Error {
         Error::from(crate::api::EndpointError::from(e))
@@ -34,3 +78,15 @@ impl From<std::io::Error> for Error {
         Error::Io(e)
     }
 }   
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Error {
+        Error::Toml(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Json(e)
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+
+use crate::{
+    api::user::Settings,
+    error::Error,
+    time_parser::{self, TimeExpr},
+};
+
+// Zone timestamps are shown and typed in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    // The system zone
+    Local,
+    Named(Tz),
+}
+
+// Renders timestamps following the time zone, date format and time format of
+// the user's Clockify settings.
+#[derive(Debug, Clone)]
+pub struct Formatter {
+    pub zone: Zone,
+    // strftime formats
+    pub date_format: String,
+    pub time_format: String,
+    // First day of the weeks of the timesheet and the weekly grid
+    pub week_start: Weekday,
+}
+
+impl Default for Formatter {
+    fn default() -> Formatter {
+        Formatter {
+            zone: Zone::Local,
+            date_format: String::from("%Y-%m-%d"),
+            time_format: String::from("%H:%M"),
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl Formatter {
+    // With local_zone the system zone is used instead of the one in settings
+    pub fn new(settings: Option<&Settings>, local_zone: bool) -> Formatter {
+        let default = Formatter::default();
+        let settings = match settings {
+            Some(settings) => settings,
+            None => return default,
+        };
+        let zone = match time_parser::user_time_zone(Some(settings)) {
+            Some(tz) if !local_zone => Zone::Named(tz),
+            _ => Zone::Local,
+        };
+        let date_format = if settings.date_format.is_empty() {
+            default.date_format
+        } else {
+            // Clockify formats look like MM/DD/YYYY
+            settings.date_format
+                .replace("YYYY", "%Y")
+                .replace("YY", "%y")
+                .replace("MM", "%m")
+                .replace("DD", "%d")
+        };
+        let time_format = match settings.time_format.as_str() {
+            "HOUR12" => String::from("%-I:%M %p"),
+            _ => default.time_format,
+        };
+        let week_start = settings.week_start.parse::<Weekday>().unwrap_or(default.week_start);
+        Formatter { zone, date_format, time_format, week_start }
+    }
+
+    pub fn naive(&self, t: DateTime<Utc>) -> NaiveDateTime {
+        match self.zone {
+            Zone::Local => t.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => t.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    // Wall clock time in the zone, the earlier one when it is ambiguous
+    pub fn to_utc(&self, t: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.zone {
+            Zone::Local => Local.from_local_datetime(&t).earliest().map(|t| t.with_timezone(&Utc)),
+            Zone::Named(tz) => tz.from_local_datetime(&t).earliest().map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    // Midnight starting date in the zone
+    pub fn start_of_day(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        self.to_utc(date.and_hms_opt(0, 0, 0)?)
+    }
+
+    pub fn local_date(&self, t: DateTime<Utc>) -> NaiveDate {
+        self.naive(t).date()
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.local_date(Utc::now())
+    }
+
+    pub fn date(&self, date: NaiveDate) -> String {
+        date.format(&self.date_format).to_string()
+    }
+
+    pub fn time(&self, t: DateTime<Utc>) -> String {
+        self.naive(t).format(&self.time_format).to_string()
+    }
+
+    pub fn date_time(&self, t: DateTime<Utc>) -> String {
+        format!("{} {}", self.date(self.local_date(t)), self.time(t))
+    }
+
+    // Weekday and date, e.g. for day headers
+    pub fn day(&self, date: NaiveDate) -> String {
+        format!("{} {}", date.format("%a"), self.date(date))
+    }
+
+    // Parse a time expression in this zone
+    pub fn parse_time(&self, input: &str, now: DateTime<Utc>, default_start: NaiveTime) -> Result<TimeExpr, Error> {
+        match self.zone {
+            Zone::Local => time_parser::parse(input, now, &Local, default_start),
+            Zone::Named(tz) => time_parser::parse(input, now, &tz, default_start),
+        }
+    }
+}
+
+// Durations as H:MM
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(time_zone: &str, date_format: &str, time_format: &str) -> Settings {
+        Settings {
+            time_zone: time_zone.to_owned(),
+            date_format: date_format.to_owned(),
+            time_format: time_format.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn user_settings() {
+        let formatter = Formatter::new(Some(&settings("America/New_York", "MM/DD/YYYY", "HOUR12")), false);
+        let t = Utc.with_ymd_and_hms(2024, 3, 13, 2, 5, 0).unwrap();
+        assert_eq!(formatter.date_time(t), "03/12/2024 10:05 PM");
+        assert_eq!(formatter.local_date(t), NaiveDate::from_ymd_opt(2024, 3, 12).unwrap());
+
+        let formatter = Formatter::new(Some(&settings("Europe/Berlin", "DD.MM.YYYY", "HOUR24")), false);
+        assert_eq!(formatter.date_time(t), "13.03.2024 03:05");
+        assert_eq!(formatter.day(NaiveDate::from_ymd_opt(2024, 3, 13).unwrap()), "Wed 13.03.2024");
+    }
+
+    #[test]
+    fn week_start() {
+        let sunday = Settings { week_start: String::from("SUNDAY"), ..Default::default() };
+        assert_eq!(Formatter::new(Some(&sunday), false).week_start, Weekday::Sun);
+        assert_eq!(Formatter::new(Some(&Settings::default()), false).week_start, Weekday::Mon);
+        assert_eq!(Formatter::new(None, false).week_start, Weekday::Mon);
+    }
+
+    #[test]
+    fn local_zone_override() {
+        let formatter = Formatter::new(Some(&settings("Europe/Berlin", "", "")), true);
+        assert_eq!(formatter.zone, Zone::Local);
+        assert_eq!(formatter.date_format, "%Y-%m-%d");
+        assert_eq!(formatter.time_format, "%H:%M");
+    }
+
+    #[test]
+    fn unknown_zone() {
+        let formatter = Formatter::new(Some(&settings("Nowhere", "", "")), false);
+        assert_eq!(formatter.zone, Zone::Local);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::minutes(5)), "0:05");
+        assert_eq!(format_duration(Duration::minutes(135)), "2:15");
+    }
+}
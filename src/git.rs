@@ -0,0 +1,134 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Current branch of the repository containing dir, read from .git/HEAD so
+// git itself is not needed. None outside a repository or on a detached HEAD.
+pub fn current_branch(dir: &Path) -> Option<String> {
+    let git_dir = find_git_dir(dir)?;
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(|branch| branch.to_owned())
+}
+
+fn find_git_dir(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let path = ancestor.join(".git");
+        if path.is_dir() {
+            return Some(path);
+        }
+        // Worktrees and submodules have a file pointing to the git directory
+        if path.is_file() {
+            let content = fs::read_to_string(&path).ok()?;
+            let git_dir = Path::new(content.trim().strip_prefix("gitdir:")?.trim());
+            return Some(ancestor.join(git_dir));
+        }
+    }
+    None
+}
+
+// Issue key such as PROJ-123 in a branch name like feature/PROJ-123-login-page
+pub fn issue_key(branch: &str) -> Option<String> {
+    let name = branch.rsplit('/').next()?;
+    let parts: Vec<&str> = name.split(['-', '_']).collect();
+    parts.windows(2).find_map(|pair| {
+        let (project, number) = (pair[0], pair[1]);
+        let is_project = project.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+            && project.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        let is_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+        if is_project && is_number {
+            Some(format!("{}-{}", project, number))
+        } else {
+            None
+        }
+    })
+}
+
+// Readable description of a branch: feature/PROJ-123-login-page becomes
+// "PROJ-123 login page"
+pub fn branch_description(branch: &str) -> String {
+    let name = branch.rsplit('/').next().unwrap_or(branch);
+    match issue_key(branch) {
+        Some(key) => {
+            let rest = name.replacen(&key, "", 1).replace(['-', '_'], " ");
+            let rest = rest.trim();
+            if rest.is_empty() {
+                key
+            } else {
+                format!("{} {}", key, rest)
+            }
+        },
+        None => name.replace(['-', '_'], " "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory of its own under the system temp dir
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clockify-git-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn issue_keys() {
+        assert_eq!(issue_key("feature/PROJ-123-login-page").as_deref(), Some("PROJ-123"));
+        assert_eq!(issue_key("PROJ-123").as_deref(), Some("PROJ-123"));
+        assert_eq!(issue_key("fix/login_AB2_7_timeout").as_deref(), Some("AB2-7"));
+        assert_eq!(issue_key("users/me/feature/WEB-1").as_deref(), Some("WEB-1"));
+        for branch in ["main", "feature/login-page", "proj-123-login", "PROJ-abc", "2024-03-release", "PROJ-"] {
+            assert_eq!(issue_key(branch), None, "{}", branch);
+        }
+    }
+
+    #[test]
+    fn branch_descriptions() {
+        assert_eq!(branch_description("feature/PROJ-123-login-page"), "PROJ-123 login page");
+        assert_eq!(branch_description("PROJ-123"), "PROJ-123");
+        assert_eq!(branch_description("fix/login_timeout"), "login timeout");
+        assert_eq!(branch_description("main"), "main");
+    }
+
+    #[test]
+    fn branch_from_head() {
+        let repo = temp_dir("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("src/ui")).unwrap();
+        fs::write(repo.join(".git/HEAD"), "ref: refs/heads/feature/PROJ-1-grid\n").unwrap();
+        assert_eq!(current_branch(&repo).as_deref(), Some("feature/PROJ-1-grid"));
+        // Found from a subdirectory
+        assert_eq!(current_branch(&repo.join("src/ui")).as_deref(), Some("feature/PROJ-1-grid"));
+
+        // Detached HEAD
+        fs::write(repo.join(".git/HEAD"), "3f2a9c1d0e\n").unwrap();
+        assert_eq!(current_branch(&repo), None);
+        fs::remove_dir_all(&repo).unwrap();
+    }
+
+    #[test]
+    fn branch_of_worktree() {
+        let dir = temp_dir("worktree");
+        let git_dir = dir.join("main/.git/worktrees/grid");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/grid\n").unwrap();
+        let worktree = dir.join("grid");
+        fs::create_dir_all(&worktree).unwrap();
+
+        // Absolute path to the git directory
+        fs::write(worktree.join(".git"), format!("gitdir: {}\n", git_dir.display())).unwrap();
+        assert_eq!(current_branch(&worktree).as_deref(), Some("grid"));
+        // Relative to the worktree
+        fs::write(worktree.join(".git"), "gitdir: ../main/.git/worktrees/grid\n").unwrap();
+        assert_eq!(current_branch(&worktree).as_deref(), Some("grid"));
+        // Not a pointer to a git directory
+        fs::write(worktree.join(".git"), "something else\n").unwrap();
+        assert_eq!(current_branch(&worktree), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,3 +2,8 @@ pub mod clockify;
 pub mod api; 
 pub mod ui; 
 pub mod error; 
+pub mod format; 
+pub mod git; 
+pub mod local_config; 
+pub mod state; 
+pub mod time_parser; 
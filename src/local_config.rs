@@ -0,0 +1,114 @@
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::Error, git};
+
+pub const FILE_NAME: &str = ".clockify.toml";
+
+// Defaults for time entries started inside a directory, read from a
+// .clockify.toml in that directory or one of its ancestors. Project, task and
+// tags are given by name (or id).
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LocalConfig {
+    pub project: Option<String>,
+    pub task: Option<String>,
+    pub tags: Vec<String>,
+    // Description template, {dir} is replaced by the directory name, {branch}
+    // and {issue} by the git branch and its issue key
+    pub description: Option<String>,
+    // Directory containing the file
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl LocalConfig {
+    // The nearest .clockify.toml from dir upwards
+    pub fn discover(dir: &Path) -> Result<Option<LocalConfig>, Error> {
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(FILE_NAME);
+            if path.is_file() {
+                let mut local_config: LocalConfig = toml::from_str(&fs::read_to_string(&path)?)?;
+                local_config.dir = ancestor.to_path_buf();
+                return Ok(Some(local_config));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn description(&self, branch: Option<&str>) -> Option<String> {
+        let dir = self.dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let issue = branch.and_then(git::issue_key).unwrap_or_default();
+        self.description
+            .as_ref()
+            .map(|template| {
+                template
+                    .replace("{dir}", &dir)
+                    .replace("{branch}", branch.unwrap_or_default())
+                    .replace("{issue}", &issue)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A directory of its own under the system temp dir
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clockify-local-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn discover_in_parent() {
+        let dir = temp_dir("parent");
+        fs::write(dir.join(FILE_NAME), "project = \"Website\"\ntags = [\"meeting\"]\n").unwrap();
+        let nested = dir.join("src/ui");
+        fs::create_dir_all(&nested).unwrap();
+
+        let local_config = LocalConfig::discover(&nested).unwrap().unwrap();
+        assert_eq!(local_config.project.as_deref(), Some("Website"));
+        assert_eq!(local_config.tags, ["meeting"]);
+        assert_eq!(local_config.task, None);
+        assert_eq!(local_config.dir, dir);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn discover_up_to_the_root() {
+        let dir = temp_dir("none");
+        assert!(LocalConfig::discover(&dir).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn discover_invalid() {
+        let dir = temp_dir("invalid");
+        fs::write(dir.join(FILE_NAME), "project = \n").unwrap();
+        assert!(LocalConfig::discover(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn descriptions() {
+        let local_config = LocalConfig {
+            description: Some(String::from("{issue} in {dir} ({branch})")),
+            dir: PathBuf::from("/home/me/website"),
+            ..Default::default()
+        };
+        assert_eq!(local_config.description(Some("feature/WEB-12-landing-page")).as_deref(), Some("WEB-12 in website (feature/WEB-12-landing-page)"));
+        // A branch without an issue key, and no branch at all
+        assert_eq!(local_config.description(Some("main")).as_deref(), Some(" in website (main)"));
+        assert_eq!(local_config.description(None).as_deref(), Some(" in website ()"));
+        assert_eq!(LocalConfig::default().description(Some("main")), None);
+    }
+}
@@ -1,37 +1,340 @@
-mod clockify;
-mod api; 
-mod ui; 
-mod error; 
-
-use crate::error::Error;
-use crate::clockify::App;
-use crate::ui::run;
-use clap::Parser;
-use std::time::Duration; 
+use clockify::{
+    api::{EndPoint, time_entry::TimeEntry},
+    error::Error,
+    git,
+    local_config::LocalConfig,
+    state::{NameCache, State},
+    time_parser::TimeExpr,
+    clockify::{App, AppMode, ConfigFile},
+    ui::{run, ScreenId},
+};
+use chrono::Utc;
+use clap::{ArgEnum, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use reqwest::blocking::Client;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     #[clap(short, long)]
-    api_key: Option<String> 
+    api_key: Option<String>,
+    /// Named profile to use, e.g. one per organization
+    #[clap(long, global = true)]
+    profile: Option<String>,
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-fn main() -> Result<(), Error> {
-    let args = Args::parse();
-    let mut app = App::new("Clockify")?;
-    if app.config.api_key.is_none() && args.api_key.is_none() {
-        Err(Error::MissingApiKey)
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start a timer, using the defaults of a .clockify.toml for anything not given
+    Start {
+        /// Description of the time entry
+        description: Option<String>,
+        /// Project name or id
+        #[clap(long)]
+        project: Option<String>,
+        /// Task name or id
+        #[clap(long)]
+        task: Option<String>,
+        /// Tag name or id, can be repeated
+        #[clap(long = "tag")]
+        tags: Vec<String>,
+        /// Start time such as 9am or -20m, or a range such as "yesterday 14:00-15:30" to add a finished entry
+        #[clap(long)]
+        at: Option<String>,
+    },
+    /// Print the running timer from the last state saved by the app, for shell prompts
+    Status {
+        /// Placeholders: {description}, {project}, {elapsed} and {today}
+        #[clap(long, default_value = "{description} {elapsed}")]
+        format: String,
+        /// Printed when no timer is running, may contain {today}
+        #[clap(long, default_value = "")]
+        idle: String,
+    },
+    /// Print a completion script for a shell
+    Completions {
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+    /// Print the cached names of a kind, used by the completion scripts
+    #[clap(hide = true)]
+    Complete {
+        #[clap(value_enum)]
+        kind: NameKind,
+    },
+    /// Stop the running timer and start a new one from a previous entry
+    Continue {
+        /// Continue the most recent finished entry
+        #[clap(long, conflicts_with = "id", required_unless_present = "id")]
+        last: bool,
+        /// Id of the time entry to continue
+        id: Option<String>,
+    },
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum NameKind {
+    Project,
+    Task,
+    Tag,
+    Profile,
+}
+
+// Options completed with cached names instead of file names
+const DYNAMIC_OPTIONS: [(&str, &str, &str); 4] = [
+    ("--project", "PROJECT", "project"),
+    ("--task", "TASK", "task"),
+    ("--tag", "TAGS", "tag"),
+    ("--profile", "PROFILE", "profile"),
+];
+
+// Shell functions printing the --profile option of the command line being
+// completed, so that the names of that profile are listed
+const BASH_PROFILE_ARGS: &str = r#"__clockify_profile_args() {
+    local i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        if [[ ${COMP_WORDS[i]} == --profile ]]; then
+            if [[ ${COMP_WORDS[i+1]} == = ]]; then
+                printf -- '--profile=%s' "${COMP_WORDS[i+2]}"
+            else
+                printf -- '--profile=%s' "${COMP_WORDS[i+1]}"
+            fi
+            return
+        fi
+    done
+}
+"#;
+
+const ZSH_PROFILE_ARGS: &str = r#"__clockify_profile_args() {
+    local i=${words[(I)--profile]}
+    if (( i )); then
+        print -r -- "--profile=${words[i+1]}"
+    else
+        i=${words[(I)--profile=*]}
+        (( i )) && print -r -- "${words[i]}"
+    fi
+}
+"#;
+
+const FISH_PROFILE_ARGS: &str = r#"function __clockify_profile_args
+    set -l words (commandline -opc)
+    set -l i (contains -i -- --profile $words)
+    and test (count $words) -gt $i
+    and echo --profile=$words[(math $i + 1)]
+    or string match -- '--profile=*' $words
+end
+"#;
+
+// Command printing the names of a kind for the profile on the command line
+fn complete_command(kind: &str) -> String {
+    format!("clockify $(__clockify_profile_args) complete {} 2>/dev/null", kind)
+}
+
+// The generated script, with project, task, tag and profile options completed
+// by `clockify complete <kind>`
+fn completions(shell: Shell) -> String {
+    let mut buffer = vec![];
+    clap_complete::generate(shell, &mut Args::command(), "clockify", &mut buffer);
+    let script = String::from_utf8_lossy(&buffer).into_owned();
+    match shell {
+        Shell::Bash => {
+            let mut lines: Vec<String> = vec![];
+            let mut kind = None;
+            for line in script.lines() {
+                match kind.take() {
+                    Some(kind) if line.contains("compgen -f") => {
+                        let indent = &line[..line.len() - line.trim_start().len()];
+                        lines.push(format!(
+                            "{}local IFS=$'\\n'; COMPREPLY=($(compgen -W \"$({})\" -- \"${{cur}}\"))",
+                            indent,
+                            complete_command(kind),
+                        ));
+                        continue;
+                    },
+                    _ => {},
+                }
+                kind = DYNAMIC_OPTIONS
+                    .iter()
+                    .find(|(option, _, _)| line.trim() == format!("{})", option))
+                    .map(|(_, _, kind)| *kind);
+                lines.push(line.to_owned());
+            }
+            format!("{}\n{}\n", BASH_PROFILE_ARGS, lines.join("\n"))
+        },
+        Shell::Zsh => {
+            let script = DYNAMIC_OPTIONS.iter().fold(script, |script, (_, value, kind)| {
+                let action = format!(":{}:{{compadd -- ${{(f)\"$({})\"}}}}'", value, complete_command(kind));
+                script.replace(&format!(":{}: '", value), &action)
+            });
+            // #compdef has to stay on the first line
+            match script.split_once('\n') {
+                Some((compdef, rest)) => format!("{}\n\n{}\n{}", compdef, ZSH_PROFILE_ARGS, rest),
+                None => script,
+            }
+        },
+        Shell::Fish => script
+            .lines()
+            .map(|line| {
+                let kind = DYNAMIC_OPTIONS
+                    .iter()
+                    .find(|(option, _, _)| line.contains(&format!(" -l {} ", &option[2..])))
+                    .map(|(_, _, kind)| *kind);
+                match kind {
+                    // Fish substitutes commands with (...) instead of $(...)
+                    Some(kind) if line.ends_with(" -r") => {
+                        let command = complete_command(kind).replace("$(", "(");
+                        format!("{} -f -a \"({})\"", line, command)
+                    },
+                    _ => line.to_owned(),
+                }
+            })
+            .fold(format!("{}\n", FISH_PROFILE_ARGS), |script, line| script + &line + "\n"),
+        _ => script,
+    }
+}
+
+fn start_entry(app: &mut App, description: Option<String>, project: Option<String>, task: Option<String>, tags: Vec<String>, at: Option<String>) -> Result<(), Error> {
+    let client = Client::new();
+    // The user's settings also give the time zone of --at
+    if app.config.user_id.is_none() || at.is_some() {
+        app.current_user(&client)?;
+    }
+    let local_config = app.local_config.clone().unwrap_or_default();
+    // The task of a .clockify.toml belongs to its project
+    let task = if project.is_none() { task.or_else(|| local_config.task.clone()) } else { task };
+    let project = project.or_else(|| local_config.project.clone());
+    let tags = if tags.is_empty() { local_config.tags.clone() } else { tags };
+    let description = description.or_else(|| local_config.description(app.git_branch.as_deref()));
+    app.apply_selections(&client, project.as_deref(), task.as_deref(), &tags, description)?;
+    app.apply_git_branch(&client)?;
+    let time = match at {
+        Some(at) => app.parse_time(&at)?,
+        None => TimeExpr::At(Utc::now()),
+    };
+    app.start_entry_at(&client, time)?;
+    let time_entry = app.get_current_entry(&client)?.ok_or(Error::MissingTimeEntry)?;
+    if time.end().is_some() {
+        println!("Added: {}", time_entry);
     } else {
-        if app.config.api_key.is_none() {
-            app.config.api_key = args.api_key;
-        }
-        let tick_rate = Duration::from_millis(150); 
-        run(&mut app, tick_rate)?; 
-        if let Some(e) = app.error {
-            println!("{:?}", e);
+        println!("Started: {}", time_entry);
+    }
+    Ok(())
+}
+
+fn continue_entry(app: &mut App, last: bool, id: Option<String>) -> Result<(), Error> {
+    let client = Client::new();
+    if app.config.user_id.is_none() {
+        app.current_user(&client)?;
+    }
+    let time_entry = match id {
+        Some(id) => TimeEntry::get(&client, &app.config, &id, None)?,
+        None if last => TimeEntry::list(&client, &app.config, None)?
+            .into_iter()
+            .find(|entry| !entry.is_running())
+            .ok_or(Error::MissingTimeEntry)?,
+        None => return Err(Error::MissingTimeEntry),
+    };
+    let time_entry = app.continue_entry(&client, &time_entry)?;
+    println!("Started: {}", time_entry);
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run_command() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run_command() -> Result<(), Error> {
+    let args = Args::parse();
+    // Printed before the config file is read, which may create it
+    if let Some(Command::Completions { shell }) = &args.command {
+        print!("{}", completions(*shell));
+        return Ok(());
+    }
+    let config_file = ConfigFile::load()?;
+    let profile = args.profile
+        .or_else(|| config_file.default_profile.clone())
+        .unwrap_or_else(|| String::from("default"));
+    // Answered from local files only, without the API
+    let cache_dir = config_file.profile(&profile).cache_dir;
+    match &args.command {
+        Some(Command::Status { format, idle }) => {
+            println!("{}", State::load(cache_dir.as_deref(), &profile)?.render(format, idle, Utc::now()));
+            return Ok(());
+        },
+        Some(Command::Complete { kind }) => {
+            let names = NameCache::load(cache_dir.as_deref(), &profile)?;
+            let names = match kind {
+                NameKind::Project => names.projects,
+                NameKind::Task => names.tasks,
+                NameKind::Tag => names.tags,
+                NameKind::Profile => config_file.profiles.keys().cloned().collect(),
+            };
+            for name in names {
+                println!("{}", name);
+            }
+            return Ok(());
+        },
+        _ => {},
+    }
+    let mut app = App::new("Clockify", config_file, profile);
+    let current_dir = std::env::current_dir()?;
+    app.local_config = LocalConfig::discover(&current_dir)?;
+    app.git_branch = git::current_branch(&current_dir);
+    match args.api_key {
+        Some(api_key) => app.config.api_key = Some(api_key), 
+        None => app.config.resolve_api_key()?, 
+    }
+    match args.command {
+        Some(Command::Start { description, project, task, tags, at }) => {
+            if app.config.api_key.is_none() {
+                return Err(Error::MissingApiKey);
+            }
+            start_entry(&mut app, description, project, task, tags, at)?;
+        },
+        Some(Command::Continue { last, id }) => {
+            if app.config.api_key.is_none() {
+                return Err(Error::MissingApiKey);
+            }
+            continue_entry(&mut app, last, id)?;
+        },
+        Some(Command::Status { .. }) | Some(Command::Complete { .. }) | Some(Command::Completions { .. }) => {},
+        None => {
+            // First run: ask for the API key in the app
+            if app.config.api_key.is_none() {
+                app.onboarding = true;
+                app.current_screen = ScreenId::Onboarding;
+                app.current_mode = AppMode::Edit;
+            }
+            let tick_rate = Duration::from_millis(150);
+            // The selections are saved even if the app stopped on an error
+            if let Err(e) = run(&mut app, tick_rate) {
+                eprintln!("{}", e);
+            }
         }
-        confy::store("clockify", app.config)?;
-        Ok(())
     }
+    app.store_config()?;
+    Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_hooks() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = completions(shell);
+            assert!(script.contains("__clockify_profile_args()") || script.contains("function __clockify_profile_args"), "{:?}", shell);
+            for (_, _, kind) in DYNAMIC_OPTIONS {
+                assert!(script.contains(&format!("__clockify_profile_args) complete {} 2>/dev/null", kind)), "{:?} {}", shell, kind);
+            }
+        }
+        assert!(completions(Shell::Zsh).starts_with("#compdef clockify\n"));
+    }
+}
@@ -0,0 +1,231 @@
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+use directories::ProjectDirs;
+use serde::{de::DeserializeOwned, Serialize, Deserialize};
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::{
+    api::time_entry::TimeEntry,
+    error::Error,
+    format::{format_duration, Formatter, Zone},
+};
+
+// Running timer and today's total as last seen by the app, so shell prompts
+// and status lines can show them without calling the API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct State {
+    pub running: Option<RunningTimer>,
+    // Day the total was computed for, in time_zone
+    pub date: Option<NaiveDate>,
+    // Seconds tracked that day by finished entries
+    pub finished_seconds: i64,
+    // Zone of the user's settings, or None for the system zone
+    pub time_zone: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunningTimer {
+    pub description: String,
+    pub project: Option<String>,
+    pub start: DateTime<Utc>,
+}
+
+impl State {
+    // State from today's (hydrated) entries and the running one
+    pub fn new(today: &[TimeEntry], running: Option<&TimeEntry>, formatter: &Formatter) -> State {
+        let finished = today
+            .iter()
+            .filter(|entry| !entry.is_running())
+            .filter_map(|entry| entry.duration())
+            .fold(Duration::zero(), |total, d| total + d);
+        State {
+            running: running.and_then(|entry| {
+                Some(RunningTimer {
+                    description: entry.description.clone().unwrap_or_default(),
+                    project: entry.project.as_ref().map(|project| project.name.clone()),
+                    start: entry.start_time()?,
+                })
+            }),
+            date: Some(formatter.today()),
+            finished_seconds: finished.num_seconds(),
+            time_zone: match formatter.zone {
+                Zone::Local => None,
+                Zone::Named(tz) => Some(tz.name().to_owned()),
+            },
+        }
+    }
+
+    // Days as seen by the app that wrote the state
+    fn formatter(&self) -> Formatter {
+        let zone = match self.time_zone.as_deref().map(str::parse::<Tz>) {
+            Some(Ok(tz)) => Zone::Named(tz),
+            _ => Zone::Local,
+        };
+        Formatter { zone, ..Formatter::default() }
+    }
+
+    pub fn load(cache_dir: Option<&Path>, profile: &str) -> Result<State, Error> {
+        load(cache_dir, &format!("state-{}.json", profile))
+    }
+
+    pub fn store(&self, cache_dir: Option<&Path>, profile: &str) -> Result<(), Error> {
+        store(cache_dir, &format!("state-{}.json", profile), self)
+    }
+
+    // Time tracked today including the running timer
+    pub fn today(&self, now: DateTime<Utc>) -> Duration {
+        let formatter = self.formatter();
+        let today = formatter.local_date(now);
+        let finished = if self.date == Some(today) {
+            Duration::seconds(self.finished_seconds)
+        } else {
+            Duration::zero()
+        };
+        let running = match &self.running {
+            Some(timer) => {
+                let midnight = formatter.start_of_day(today).unwrap_or(timer.start);
+                now - timer.start.max(midnight)
+            },
+            None => Duration::zero(),
+        };
+        finished + running
+    }
+
+    // Replace {description}, {project}, {elapsed} and {today} in format,
+    // or return idle when no timer is running
+    pub fn render(&self, format: &str, idle: &str, now: DateTime<Utc>) -> String {
+        let today = format_duration(self.today(now));
+        match &self.running {
+            Some(timer) => format
+                .replace("{description}", &timer.description)
+                .replace("{project}", timer.project.as_deref().unwrap_or_default())
+                .replace("{elapsed}", &format_duration(now - timer.start))
+                .replace("{today}", &today),
+            None => idle.replace("{today}", &today),
+        }
+    }
+}
+
+// Names of the projects, tasks (of the current project) and tags last loaded
+// by the app, for shell completion
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NameCache {
+    pub projects: Vec<String>,
+    pub tasks: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl NameCache {
+    pub fn load(cache_dir: Option<&Path>, profile: &str) -> Result<NameCache, Error> {
+        load(cache_dir, &format!("names-{}.json", profile))
+    }
+
+    pub fn store(&self, cache_dir: Option<&Path>, profile: &str) -> Result<(), Error> {
+        store(cache_dir, &format!("names-{}.json", profile), self)
+    }
+}
+
+// File in cache_dir, or else in the user's cache directory
+fn cache_path(cache_dir: Option<&Path>, file_name: &str) -> Result<PathBuf, Error> {
+    match cache_dir {
+        Some(dir) => Ok(dir.join(file_name)),
+        None => {
+            let dirs = ProjectDirs::from("rs", "", "clockify").ok_or(Error::MissingData)?;
+            Ok(dirs.cache_dir().join(file_name))
+        },
+    }
+}
+
+// A missing or unreadable file is the same as an empty one
+fn load<T: DeserializeOwned + Default>(cache_dir: Option<&Path>, file_name: &str) -> Result<T, Error> {
+    match fs::read_to_string(cache_path(cache_dir, file_name)?) {
+        Ok(content) => Ok(serde_json::from_str(&content).unwrap_or_default()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(Error::from(e)),
+    }
+}
+
+fn store<T: Serialize>(cache_dir: Option<&Path>, file_name: &str, value: &T) -> Result<(), Error> {
+    let path = cache_path(cache_dir, file_name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(value).map_err(|_| Error::MissingData)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::time_entry::{format_timestamp, TimeInterval};
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, minute, 0).unwrap()
+    }
+
+    // 1:30 finished on 2024-03-13 in Berlin, and a timer started at 14:00 UTC
+    fn state(running: bool) -> State {
+        State {
+            running: running.then(|| RunningTimer {
+                description: String::from("Landing page"),
+                project: Some(String::from("Website")),
+                start: utc(13, 14, 0),
+            }),
+            date: NaiveDate::from_ymd_opt(2024, 3, 13),
+            finished_seconds: 90 * 60,
+            time_zone: Some(String::from("Europe/Berlin")),
+        }
+    }
+
+    #[test]
+    fn render_running() {
+        let rendered = state(true).render("{project}: {description} {elapsed} ({today})", "idle", utc(13, 14, 45));
+        assert_eq!(rendered, "Website: Landing page 0:45 (2:15)");
+        // Unknown placeholders stay as they are
+        assert_eq!(state(true).render("{task} {elapsed}", "idle", utc(13, 15, 0)), "{task} 1:00");
+    }
+
+    #[test]
+    fn render_without_project() {
+        let mut state = state(true);
+        state.running.as_mut().unwrap().project = None;
+        assert_eq!(state.render("[{project}] {description}", "idle", utc(13, 15, 0)), "[] Landing page");
+    }
+
+    #[test]
+    fn render_idle() {
+        assert_eq!(state(false).render("{description}", "no timer ({today})", utc(13, 20, 0)), "no timer (1:30)");
+        assert_eq!(state(false).render("{description}", "", utc(13, 20, 0)), "");
+        assert_eq!(State::default().render("{description}", "{today}", utc(13, 20, 0)), "0:00");
+    }
+
+    #[test]
+    fn today_in_the_state_zone() {
+        // 23:30 UTC is already the next day in Berlin, so only the running
+        // time since midnight there counts
+        assert_eq!(state(true).today(utc(13, 22, 30)), Duration::minutes(90 + 8 * 60 + 30));
+        assert_eq!(state(true).today(utc(13, 23, 30)), Duration::minutes(30));
+        assert_eq!(state(false).today(utc(13, 23, 30)), Duration::zero());
+    }
+
+    #[test]
+    fn new_from_entries() {
+        let entry = |start: DateTime<Utc>, minutes: Option<i64>| TimeEntry {
+            description: Some(String::from("Review")),
+            time_interval: Some(TimeInterval {
+                start: Some(format_timestamp(start)),
+                end: minutes.map(|m| format_timestamp(start + Duration::minutes(m))),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let finished = [entry(utc(13, 8, 0), Some(60)), entry(utc(13, 10, 0), Some(30))];
+        let running = entry(utc(13, 14, 0), None);
+        let formatter = Formatter { zone: Zone::Named(chrono_tz::Europe::Berlin), ..Formatter::default() };
+        let state = State::new(&finished, Some(&running), &formatter);
+        assert_eq!(state.finished_seconds, 90 * 60);
+        assert_eq!(state.time_zone.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(state.running.as_ref().map(|timer| timer.start), Some(utc(13, 14, 0)));
+        assert_eq!(state.render("{description}", "idle", utc(13, 15, 0)), "Review");
+    }
+}
@@ -0,0 +1,448 @@
+// Natural-language time expressions for starting and entering time entries.
+//
+// An expression is an optional day followed by a time, a time range and/or a
+// duration:
+//
+//   now, -20m, +1h, 20m ago          relative to now
+//   9am, 14:00, 9:30pm, noon         today at that time
+//   yesterday 14:00-15:30            a range on a day
+//   last friday 2h                   a duration from the start of the day
+//   monday 9am 1h30m                 a duration from a time
+//   2024-03-05 9-17                  ISO dates, bare hours
+//
+// Times are wall clock times in the given zone, usually the user's
+// Settings.time_zone.
+
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+
+use crate::{api::user::Settings, error::Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeExpr {
+    At(DateTime<Utc>),
+    Range(DateTime<Utc>, DateTime<Utc>),
+}
+
+impl TimeExpr {
+    pub fn start(&self) -> DateTime<Utc> {
+        match self {
+            TimeExpr::At(start) | TimeExpr::Range(start, _) => *start,
+        }
+    }
+
+    pub fn end(&self) -> Option<DateTime<Utc>> {
+        match self {
+            TimeExpr::At(_) => None,
+            TimeExpr::Range(_, end) => Some(*end),
+        }
+    }
+}
+
+// Zone of the user's settings, if it is a known IANA zone
+pub fn user_time_zone(settings: Option<&Settings>) -> Option<Tz> {
+    settings.and_then(|s| s.time_zone.parse().ok())
+}
+
+fn invalid(input: &str, reason: &str) -> Error {
+    Error::InvalidTime(format!("{}: {}", input, reason))
+}
+
+// Parse input relative to now. default_start is the time used when only a
+// day and a duration are given.
+pub fn parse<Z: TimeZone>(input: &str, now: DateTime<Utc>, tz: &Z, default_start: NaiveTime) -> Result<TimeExpr, Error> {
+    let tokens = tokenize(input);
+    if tokens.is_empty() {
+        return Err(invalid(input, "empty"));
+    }
+    let today = now.with_timezone(tz).date_naive();
+    let mut rest: &[String] = &tokens;
+
+    // Relative to now
+    match rest {
+        [word] if word == "now" => return Ok(TimeExpr::At(now)),
+        [offset] if offset.starts_with('-') || offset.starts_with('+') => {
+            let duration = parse_duration_with_unit(&offset[1..]).ok_or_else(|| invalid(input, "unknown offset"))?;
+            return Ok(TimeExpr::At(if offset.starts_with('-') { now - duration } else { now + duration }));
+        },
+        [duration, ago] if ago == "ago" => {
+            let duration = parse_duration_with_unit(duration).ok_or_else(|| invalid(input, "unknown duration"))?;
+            return Ok(TimeExpr::At(now - duration));
+        },
+        _ => {},
+    }
+
+    // Day
+    let (day, used) = parse_day(rest, today).ok_or_else(|| invalid(input, "unknown day"))?;
+    rest = &rest[used..];
+    if rest.first().is_some_and(|word| word == "at") {
+        rest = &rest[1..];
+    }
+
+    let at = |date: NaiveDate, time: NaiveTime| -> Result<DateTime<Utc>, Error> {
+        tz.from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(|| invalid(input, "time does not exist in this time zone"))
+    };
+
+    match rest {
+        [] => match day {
+            Some(day) => Ok(TimeExpr::At(at(day, default_start)?)),
+            None => Err(invalid(input, "nothing to parse")),
+        },
+        [word] => {
+            if let Some((start, end)) = parse_time_range(word) {
+                let day = day.unwrap_or(today);
+                let start = at(day, start)?;
+                let mut end = at(day, end)?;
+                // 22:00-01:00 ends on the next day
+                if end <= start {
+                    end = at(day + Duration::days(1), end.with_timezone(tz).time())?;
+                }
+                Ok(TimeExpr::Range(start, end))
+            } else if let Some(time) = parse_time(word) {
+                Ok(TimeExpr::At(at(day.unwrap_or(today), time)?))
+            } else if let Some(duration) = parse_duration_with_unit(word) {
+                match day {
+                    Some(day) => {
+                        let start = at(day, default_start)?;
+                        Ok(TimeExpr::Range(start, start + duration))
+                    },
+                    // Time just spent
+                    None => Ok(TimeExpr::Range(now - duration, now)),
+                }
+            } else {
+                Err(invalid(input, "unknown time"))
+            }
+        },
+        [time, duration] => {
+            let time = parse_time(time).ok_or_else(|| invalid(input, "unknown time"))?;
+            let duration = parse_duration_with_unit(duration).ok_or_else(|| invalid(input, "unknown duration"))?;
+            let start = at(day.unwrap_or(today), time)?;
+            Ok(TimeExpr::Range(start, start + duration))
+        },
+        _ => Err(invalid(input, "too many words")),
+    }
+}
+
+// Lowercase words, with ranges written as "9am - 5pm" or "9 to 5" joined
+fn tokenize(input: &str) -> Vec<String> {
+    let words: Vec<String> = input.split_whitespace().map(|w| w.to_lowercase()).collect();
+    let mut tokens: Vec<String> = vec![];
+    let mut i = 0;
+    while i < words.len() {
+        let word = &words[i];
+        if (word == "-" || word == "to") && !tokens.is_empty() && i + 1 < words.len() {
+            let previous = tokens.pop().unwrap_or_default();
+            tokens.push(format!("{}-{}", previous, words[i + 1]));
+            i += 2;
+        } else {
+            tokens.push(word.clone());
+            i += 1;
+        }
+    }
+    tokens
+}
+
+// Day at the start of tokens and the number of words it used. None means the
+// tokens did not start with a day.
+fn parse_day(tokens: &[String], today: NaiveDate) -> Option<(Option<NaiveDate>, usize)> {
+    let first = match tokens.first() {
+        Some(first) => first.as_str(),
+        None => return Some((None, 0)),
+    };
+    match first {
+        "today" => return Some((Some(today), 1)),
+        "yesterday" => return Some((Some(today - Duration::days(1)), 1)),
+        "tomorrow" => return Some((Some(today + Duration::days(1)), 1)),
+        "last" => {
+            let weekday = parse_weekday(tokens.get(1)?)?;
+            return Some((Some(previous_weekday(today, weekday, false)), 2));
+        },
+        _ => {},
+    }
+    if let Some(weekday) = parse_weekday(first) {
+        return Some((Some(previous_weekday(today, weekday, true)), 1));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+        return Some((Some(date), 1));
+    }
+    Some((None, 0))
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thur" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+// Most recent weekday before today, or on today if include_today
+fn previous_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let mut days = (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    if days == 0 && !include_today {
+        days = 7;
+    }
+    today - Duration::days(days as i64)
+}
+
+// 9, 9am, 9:30, 9:30pm, 14:00, noon, midnight
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {},
+    }
+    let (clock, meridiem) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(false))
+    } else if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(true))
+    } else {
+        (word, None)
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        Some(_) => return None,
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    let hour = match meridiem {
+        Some(pm) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour % 12 + if pm { 12 } else { 0 }
+        },
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+// 14:00-15:30 or 9am-5pm
+fn parse_time_range(word: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = word.split_once('-')?;
+    Some((parse_time(start)?, parse_time(end)?))
+}
+
+// Durations like "1:30", "1.5", "1h30m", "90m" or "2h", as typed into a
+// cell of the weekly grid
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return None;
+    }
+    if let Some((hours, minutes)) = input.split_once(':') {
+        let hours: i64 = hours.parse().ok()?;
+        let minutes: i64 = minutes.parse().ok()?;
+        if hours < 0 || !(0..60).contains(&minutes) {
+            return None;
+        }
+        return Some(Duration::minutes(hours * 60 + minutes));
+    }
+    if let Ok(hours) = input.parse::<f64>() {
+        if !hours.is_finite() || hours < 0.0 {
+            return None;
+        }
+        return Some(Duration::minutes((hours * 60.0).round() as i64));
+    }
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in input.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' => {
+                let value: f64 = number.parse().ok()?;
+                let minutes = if c == 'h' { value * 60.0 } else { value };
+                total += Duration::minutes(minutes.round() as i64);
+                number.clear();
+            }
+            ' ' => {}
+            _ => return None,
+        }
+    }
+    if number.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+// Durations need a unit here so that 9 or 9:30 stay times
+fn parse_duration_with_unit(word: &str) -> Option<Duration> {
+    if word.ends_with('h') || word.ends_with('m') {
+        parse_duration(word)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    // Wednesday 2024-03-13 16:45 in Berlin (UTC+1)
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 13, 15, 45, 0).unwrap()
+    }
+
+    fn nine() -> NaiveTime {
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+    }
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn parse_berlin(input: &str) -> Result<TimeExpr, Error> {
+        parse(input, now(), &Berlin, nine())
+    }
+
+    fn at(input: &str) -> DateTime<Utc> {
+        match parse_berlin(input) {
+            Ok(TimeExpr::At(t)) => t,
+            other => panic!("{} parsed as {:?}", input, other),
+        }
+    }
+
+    fn range(input: &str) -> (DateTime<Utc>, DateTime<Utc>) {
+        match parse_berlin(input) {
+            Ok(TimeExpr::Range(start, end)) => (start, end),
+            other => panic!("{} parsed as {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn now_and_offsets() {
+        assert_eq!(at("now"), now());
+        assert_eq!(at("NOW"), now());
+        assert_eq!(at("-20m"), now() - Duration::minutes(20));
+        assert_eq!(at("+1h"), now() + Duration::hours(1));
+        assert_eq!(at("-1h30m"), now() - Duration::minutes(90));
+        assert_eq!(at("20m ago"), now() - Duration::minutes(20));
+        assert_eq!(at("2h ago"), now() - Duration::hours(2));
+    }
+
+    #[test]
+    fn times_today() {
+        assert_eq!(at("9am"), utc(3, 13, 8, 0));
+        assert_eq!(at("9"), utc(3, 13, 8, 0));
+        assert_eq!(at("9:30"), utc(3, 13, 8, 30));
+        assert_eq!(at("14:00"), utc(3, 13, 13, 0));
+        assert_eq!(at("9:30pm"), utc(3, 13, 20, 30));
+        assert_eq!(at("12am"), utc(3, 12, 23, 0));
+        assert_eq!(at("12pm"), utc(3, 13, 11, 0));
+        assert_eq!(at("noon"), utc(3, 13, 11, 0));
+        assert_eq!(at("midnight"), utc(3, 12, 23, 0));
+        assert_eq!(at("today 9am"), utc(3, 13, 8, 0));
+        assert_eq!(at("today at 9am"), utc(3, 13, 8, 0));
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(at("yesterday 14:00"), utc(3, 12, 13, 0));
+        assert_eq!(at("tomorrow 9am"), utc(3, 14, 8, 0));
+        // Bare days start at the default start
+        assert_eq!(at("yesterday"), utc(3, 12, 8, 0));
+        assert_eq!(at("2024-03-05 10:15"), utc(3, 5, 9, 15));
+    }
+
+    #[test]
+    fn weekdays() {
+        // Today is a Wednesday
+        assert_eq!(at("wednesday 9am"), utc(3, 13, 8, 0));
+        assert_eq!(at("last wednesday 9am"), utc(3, 6, 8, 0));
+        assert_eq!(at("monday 9am"), utc(3, 11, 8, 0));
+        assert_eq!(at("last monday 9am"), utc(3, 11, 8, 0));
+        assert_eq!(at("fri 9am"), utc(3, 8, 8, 0));
+        assert_eq!(at("Thursday 9am"), utc(3, 7, 8, 0));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range("yesterday 14:00-15:30"), (utc(3, 12, 13, 0), utc(3, 12, 14, 30)));
+        assert_eq!(range("9am-5pm"), (utc(3, 13, 8, 0), utc(3, 13, 16, 0)));
+        assert_eq!(range("9am - 5pm"), (utc(3, 13, 8, 0), utc(3, 13, 16, 0)));
+        assert_eq!(range("9 to 17"), (utc(3, 13, 8, 0), utc(3, 13, 16, 0)));
+        assert_eq!(range("2024-03-05 9-17"), (utc(3, 5, 8, 0), utc(3, 5, 16, 0)));
+        // Ends after midnight
+        assert_eq!(range("yesterday 22:00-1:00"), (utc(3, 12, 21, 0), utc(3, 13, 0, 0)));
+    }
+
+    #[test]
+    fn durations() {
+        // From the default start of the day
+        assert_eq!(range("last friday 2h"), (utc(3, 8, 8, 0), utc(3, 8, 10, 0)));
+        assert_eq!(range("monday 9:30 1h30m"), (utc(3, 11, 8, 30), utc(3, 11, 10, 0)));
+        assert_eq!(range("14:00 45m"), (utc(3, 13, 13, 0), utc(3, 13, 13, 45)));
+        assert_eq!(range("1.5h"), (now() - Duration::minutes(90), now()));
+    }
+
+    #[test]
+    fn default_start() {
+        let start = NaiveTime::from_hms_opt(8, 30, 0).unwrap();
+        assert_eq!(
+            parse("yesterday 1h", now(), &Berlin, start).unwrap(),
+            TimeExpr::Range(utc(3, 12, 7, 30), utc(3, 12, 8, 30))
+        );
+    }
+
+    #[test]
+    fn daylight_saving_time() {
+        // Berlin moves to UTC+2 on 2024-03-31 at 02:00
+        let after = Utc.with_ymd_and_hms(2024, 4, 2, 12, 0, 0).unwrap();
+        assert_eq!(parse("9am", after, &Berlin, nine()).unwrap(), TimeExpr::At(utc(4, 2, 7, 0)));
+        assert_eq!(
+            parse("sunday 1:00-4:00", after, &Berlin, nine()).unwrap(),
+            TimeExpr::Range(utc(3, 31, 0, 0), utc(3, 31, 2, 0))
+        );
+        assert!(parse("sunday 2:30", after, &Berlin, nine()).is_err());
+    }
+
+    #[test]
+    fn other_time_zones() {
+        assert_eq!(parse("9am", now(), &Utc, nine()).unwrap(), TimeExpr::At(utc(3, 13, 9, 0)));
+        assert_eq!(
+            parse("9am", now(), &chrono_tz::America::New_York, nine()).unwrap(),
+            TimeExpr::At(utc(3, 13, 13, 0))
+        );
+        // Already Thursday in Tokyo
+        assert_eq!(
+            parse("today 9am", now(), &chrono_tz::Asia::Tokyo, nine()).unwrap(),
+            TimeExpr::At(utc(3, 14, 0, 0))
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        for input in ["", "   ", "soon", "25:00", "9:60", "13pm", "0am", "9:3", "last", "last week", "-20", "-", "yesterday 9am 2h extra", "9am foo"] {
+            assert!(parse_berlin(input).is_err(), "{} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn user_settings_zone() {
+        let settings = Settings { time_zone: String::from("Europe/Berlin"), ..Default::default() };
+        assert_eq!(user_time_zone(Some(&settings)), Some(Berlin));
+        let settings = Settings { time_zone: String::from("Mars/Olympus"), ..Default::default() };
+        assert_eq!(user_time_zone(Some(&settings)), None);
+        assert_eq!(user_time_zone(None), None);
+    }
+
+    #[test]
+    fn duration_values() {
+        for (input, minutes) in [("1:30", 90), ("0:05", 5), ("90m", 90), ("1.5h", 90), ("1.5", 90), ("2", 120), ("1h30m", 90), ("1h 30m", 90), (" 2H ", 120), ("0", 0)] {
+            assert_eq!(parse_duration(input), Some(Duration::minutes(minutes)), "{}", input);
+        }
+        for input in ["", "abc", "1:60", "1:-5", "-1", "-1h", "1x", "1h30", "h", "inf", "NaN"] {
+            assert_eq!(parse_duration(input), None, "{} should not parse", input);
+        }
+    }
+}
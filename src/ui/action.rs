@@ -0,0 +1,91 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use tui::layout::Rect;
+use crate::{
+    clockify::{App, AppMode},
+    ui::ScreenId,
+};
+
+// What a key, click or tick asks of the app. Events become actions here,
+// update() applies them to the state and returns the requests to make.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Show(ScreenId),
+    // The previous screen, and the one left with Back
+    Back,
+    Forward,
+    SetMode(AppMode),
+    // Move the highlight of the current list, form or grid
+    Next,
+    Previous,
+    Left,
+    Right,
+    // Enter on the current screen: select, toggle, save or submit
+    Confirm,
+    Clear,
+    // Typing in Edit or Search mode
+    Type(char),
+    Backspace,
+    Refresh,
+    StartTimer,
+    StopTimer,
+    UpdateEntry,
+    // Start the highlighted time entry again
+    ContinueEntry,
+    ToggleTimesheet,
+    EditFilter,
+    PreviousWeek,
+    NextWeek,
+    // With the terminal area at the time of the event
+    Mouse(MouseEvent, Rect),
+    Tick,
+}
+
+// The action of a key on the current screen and mode, if any
+pub fn from_key(app: &App, key: KeyEvent) -> Option<Action> {
+    if key.modifiers == KeyModifiers::CONTROL {
+        return (key.code == KeyCode::Char('q')).then_some(Action::Quit);
+    }
+    match app.current_mode {
+        AppMode::Edit | AppMode::Search => match key.code {
+            KeyCode::Esc if key.modifiers == KeyModifiers::NONE => Some(Action::SetMode(AppMode::Navigation)),
+            KeyCode::Char(c) => Some(Action::Type(c)),
+            KeyCode::Backspace => Some(Action::Backspace),
+            KeyCode::Enter => Some(Action::Confirm),
+            _ => None,
+        },
+        AppMode::Navigation if key.modifiers == KeyModifiers::NONE => {
+            // Keys of the screen take precedence over the global ones
+            let screen = app.current_screen.screen();
+            let global: &[(KeyCode, Action)] = if screen.global_keys() { &GLOBAL_KEYS } else { &[] };
+            screen.keymap()
+                .iter()
+                .chain(global)
+                .find(|(code, _)| *code == key.code)
+                .map(|(_, action)| *action)
+        },
+        AppMode::Navigation => None,
+    }
+}
+
+// Keys that work on every screen
+const GLOBAL_KEYS: [(KeyCode, Action); 18] = [
+    (KeyCode::Char('w'), Action::Show(ScreenId::WorkspaceSelection)),
+    (KeyCode::Char('t'), Action::Show(ScreenId::TimeEntrySelection)),
+    (KeyCode::Char('p'), Action::Show(ScreenId::ProjectSelection)),
+    (KeyCode::Char('g'), Action::Show(ScreenId::TagSelection)),
+    (KeyCode::Char('y'), Action::Show(ScreenId::TaskSelection)),
+    (KeyCode::Char('d'), Action::Show(ScreenId::DescriptionEdit)),
+    (KeyCode::Char('b'), Action::Show(ScreenId::WeeklyGrid)),
+    (KeyCode::Char('o'), Action::Show(ScreenId::ProfileSelection)),
+    (KeyCode::Char('m'), Action::Show(ScreenId::TimeInput)),
+    (KeyCode::Char('h'), Action::Show(ScreenId::Home)),
+    (KeyCode::Esc, Action::Back),
+    (KeyCode::Backspace, Action::Back),
+    (KeyCode::Tab, Action::Forward),
+    (KeyCode::Char('i'), Action::SetMode(AppMode::Edit)),
+    (KeyCode::Char('/'), Action::SetMode(AppMode::Search)),
+    (KeyCode::Char('u'), Action::UpdateEntry),
+    (KeyCode::Char('s'), Action::StartTimer),
+    (KeyCode::Char('e'), Action::StopTimer),
+];
@@ -1,20 +1,42 @@
 use tui::{
-    backend::Backend, 
+    buffer::Buffer, 
     layout::{Constraint, Direction, Layout, Rect}, 
-    style::{Modifier, Style}, 
+    style::{Color, Modifier, Style}, 
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, Paragraph, ListState},
-    Frame
+    widgets::{Block, Borders, List, ListItem, Paragraph, ListState, StatefulWidget, Widget},
 }; 
-use std::fmt::Display;
-use crossterm::event::{KeyCode, KeyEvent};
-use aho_corasick::AhoCorasickBuilder;
+use std::{
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+use chrono::NaiveDate;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use crate::{
+    api::time_entry::TimeEntryFilter,
+    ui::{action::Action, fuzzy::fuzzy_search},
+};
 
-use crate::clockify::AppMode;
+// Two clicks on the same row within this window count as a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
 
 pub trait Component {
-   fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect);
-   fn key_event(&mut self, key: KeyEvent, mode: &AppMode);
+   fn render(&self, area: Rect, buf: &mut Buffer);
+}
+
+// The list of a screen, whatever its items, as driven by actions
+pub trait ListControl {
+    fn next(&mut self);
+    fn previous(&mut self);
+    // Select or unselect the highlighted item and end the search
+    fn toggle(&mut self);
+    fn clear_selected(&mut self);
+    // Edit the search text, highlighting the best match
+    fn search_push(&mut self, c: char);
+    fn search_pop(&mut self);
+    // Mouse events may translate into an action (e.g. double-click -> Confirm)
+    // so that screens can handle clicks and keys the same way. area is where
+    // the rows are drawn.
+    fn mouse_event(&mut self, mouse: MouseEvent, area: Rect) -> Option<Action>;
 }
 
 #[derive(Debug, Clone)]
@@ -24,41 +46,20 @@ pub struct InputBox {
 }
 
 impl Component for InputBox {
-    fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+    fn render(&self, area: Rect, buf: &mut Buffer) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(5)
             .constraints(
                 [
-                Constraint::Min(1), 
+                Constraint::Length(1), 
                 Constraint::Min(0),
                 ].as_ref()
             ).split(area); 
-        f.render_widget(Paragraph::new(self.prompt.clone()), chunks[0]); 
-        f.render_widget(Paragraph::new(self.text.clone()), chunks[1]); 
-    }
-
-    fn key_event(&mut self, key: KeyEvent, mode: &AppMode) {
-        match mode {
-            AppMode::Edit => {
-                match key.code {
-                    KeyCode::Char(c) => {
-                        self.text = format!("{}{}", self.text, c); 
-                    },
-                    KeyCode::Backspace => {
-                        self.text.pop();
-                    }, 
-                    _ => {}
-                }
-            },
-            AppMode::Navigation => {
-                if let KeyCode::Char(c) = key.code {
-                    if c == 'c' { self.text = String::new() }
-                }
-            }, 
-            _ => {}
-        }
+        Paragraph::new(self.prompt.clone()).render(chunks[0], buf); 
+        Paragraph::new(self.text.clone()).render(chunks[1], buf); 
     }
+
 }
 
 impl From<&str> for InputBox {
@@ -86,18 +87,14 @@ pub struct StatefulList<T: Display + Id + Clone> {
     pub multiselect: bool, 
     pub title: String, 
     pub state: ListState, 
-    pub items: Vec<T>
-}
-
-pub trait Id {
-    fn id(&self) -> String; 
+    pub items: Vec<T>, 
+    // When rendered with extra rows (e.g. headers), the item shown on each
+    // row, see Screen::refresh_rows
+    pub rows: Option<Vec<Option<usize>>>, 
+    last_click: Option<(usize, Instant)>, 
 }
 
-#[derive(Debug, Copy, Clone)]
-struct Match<T> {
-    obj: T,
-    count: usize
-}
+pub use crate::api::Id;
 
 impl<T: Display + Id + Clone> StatefulList<T> {
     pub fn with_items(items: Vec<T>, title: String, multiselect: bool) -> StatefulList<T> {
@@ -107,45 +104,43 @@ impl<T: Display + Id + Clone> StatefulList<T> {
             multiselect, 
             state: ListState::default(), 
             items,
-            title
+            title, 
+            rows: None, 
+            last_click: None, 
+        }
+    }
+
+    pub fn visible_items(&self) -> Vec<T> {
+        if self.search_text.is_empty() {
+            self.items.to_vec()
+        } else {
+            self.search(&self.search_text)
         }
     }
 
     pub fn search(&self, query: &str) -> Vec<T> {
-        let vec_string: Vec<String> = self.items
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>();
-        let matches = Self::find_matches(query.to_owned(), &vec_string);
-        
-        Self::filter_by_matches(&self.items, &matches)
-    }
-
-    fn find_matches(query: String, vector: &[String]) -> Vec<usize> {
-        let patterns = query.split_whitespace();
-        let ac = AhoCorasickBuilder::new()
-            .ascii_case_insensitive(true)
-            .build(patterns);
-        vector
-            .iter()
-            .map(|string| {
-                ac.find_iter(string).count()
-            }).collect::<Vec<usize>>()
-    }
-
-    fn filter_by_matches(filter_vec: &[T], match_vec: &[usize]) -> Vec<T> {
-        let zip = filter_vec.iter().zip(match_vec.iter());
-        let mut matches: Vec<Match<T>> = zip
-            .map(|(x,m)| Match {
-                obj: x.clone(), 
-                count: *m,
-            }).collect::<Vec<Match<T>>>();
-        matches.sort_by(|a, b| b.count.cmp(&a.count));
-        return matches
+        self.search_with_indices(query)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect::<Vec<T>>()
+    }
+
+    // Items matching every word of the query, best match first, along with
+    // the char indices that matched for highlighting.
+    pub fn search_with_indices(&self, query: &str) -> Vec<(T, Vec<usize>)> {
+        let mut matches = self.items
             .iter()
-            .filter(|m| m.count > 0)
-            .map(|m| m.obj.clone())
-            .collect::<Vec<T>>();
+            .filter_map(|item| {
+                let text = item.to_string();
+                fuzzy_search(query, &text).map(|m| (item, text.chars().count(), m))
+            })
+            .collect::<Vec<_>>();
+        // Ties go to the shorter text, then to the original order
+        matches.sort_by(|a, b| b.2.score.cmp(&a.2.score).then(a.1.cmp(&b.1)));
+        matches
+            .into_iter()
+            .map(|(item, _, m)| (item.clone(), m.indices))
+            .collect::<Vec<(T, Vec<usize>)>>()
     }
 
     pub fn clear_selected(&mut self) {
@@ -153,7 +148,7 @@ impl<T: Display + Id + Clone> StatefulList<T> {
     }
 
     pub fn get_by_id(&self, id: String) -> Option<&T> {
-        return self.items.iter().find(|x| x.id() == id);
+        self.items.iter().find(|x| x.id() == id)
     }
 
     pub fn toggle_highlighted(&mut self) {
@@ -170,30 +165,34 @@ impl<T: Display + Id + Clone> StatefulList<T> {
         }
     }
 
+    // Item indices in the order they are displayed
+    fn display_order(&self) -> Vec<usize> {
+        match &self.rows {
+            Some(rows) => rows.iter().flatten().copied().collect(), 
+            None => (0..self.items.len()).collect(), 
+        }
+    }
+
     pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.items.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0, 
+        let order = self.display_order();
+        if order.is_empty() {
+            return;
+        }
+        let i = match self.state.selected().and_then(|s| order.iter().position(|i| *i == s)) {
+            Some(p) => order[(p + 1) % order.len()], 
+            None => order[0], 
         }; 
         self.state.select(Some(i));
     }
 
     pub fn previous(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.items.len() - 1 
-                } else {
-                    i - 1
-                }
-            }
-            None => 0, 
+        let order = self.display_order();
+        if order.is_empty() {
+            return;
+        }
+        let i = match self.state.selected().and_then(|s| order.iter().position(|i| *i == s)) {
+            Some(p) => order[(p + order.len() - 1) % order.len()], 
+            None => order[0], 
         }; 
         self.state.select(Some(i))
     }
@@ -201,13 +200,9 @@ impl<T: Display + Id + Clone> StatefulList<T> {
     pub fn get_highlighted_item(&self) -> Option<&T> {
         match self.state.selected() {
             Some(x) => {
-                let items : Vec<T> = if self.search_text.is_empty() {
-                    self.items.to_vec()
-                } else {
-                    self.search(&self.search_text)
-                };
-                let highlighted_item = items.get(x).unwrap();
-                return self.items.iter().find(|x| x.id() == highlighted_item.id());
+                let items : Vec<T> = self.visible_items();
+                let highlighted_item = items.get(x)?;
+                self.items.iter().find(|x| x.id() == highlighted_item.id())
             }, 
             None => {
                 None
@@ -216,89 +211,310 @@ impl<T: Display + Id + Clone> StatefulList<T> {
     }
 
     pub fn get_selected_item(&self) -> Option<&T> {
-        return self.items.iter().filter(|x| self.selected.contains(&x.id())).collect::<Vec<&T>>().get(0).cloned();
+        self.items.iter().find(|x| self.selected.contains(&x.id()))
     }
 
     pub fn get_selected_items(&self) -> Vec<&T> {
-        return self.items.iter().filter(|x| self.selected.contains(&x.id())).collect::<Vec<&T>>();
+        self.items.iter().filter(|x| self.selected.contains(&x.id())).collect::<Vec<&T>>()
+    }
+
+    // Row of the highlighted item as rendered
+    pub fn selected_row(&self) -> Option<usize> {
+        let selected = self.state.selected()?;
+        match &self.rows {
+            Some(rows) => rows.iter().position(|i| *i == Some(selected)), 
+            None => Some(selected), 
+        }
+    }
+
+    // Index into visible_items() of the row at a terminal position, with the
+    // rows drawn in area
+    pub fn index_at(&self, area: Rect, column: u16, row: u16) -> Option<usize> {
+        if column < area.x || column >= area.x + area.width || row < area.y || row >= area.y + area.height {
+            return None;
+        }
+        let index = scroll_offset(self.selected_row(), area.height) + (row - area.y) as usize;
+        match &self.rows {
+            Some(rows) => rows.get(index).copied().flatten(), 
+            None if index < self.visible_items().len() => Some(index), 
+            None => None, 
+        }
     }
 }
 
 impl<T: Display + Id + Clone> Component for StatefulList<T> {
-    fn render<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(5)
-            .constraints(
-                [
-                Constraint::Min(1), 
-                Constraint::Min(0),
-                ].as_ref()
-            ).split(area); 
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let chunks = list_chunks(area);
         let mut title = self.title.clone(); 
         if !self.search_text.is_empty() {
             title = format!("{}{}", title, self.search_text);
         }
-        f.render_widget(Paragraph::new(title), chunks[0]); 
-        let items : Vec<T> = if self.search_text.is_empty() {
-            self.items.to_vec()
+        Paragraph::new(title).render(chunks[0], buf); 
+        let block = Block::default().borders(Borders::ALL);
+        let items : Vec<(T, Vec<usize>)> = if self.search_text.is_empty() {
+            self.items.iter().map(|i| (i.clone(), vec![])).collect()
         } else {
-            self.search(&self.search_text)
+            self.search_with_indices(&self.search_text)
         };
+        let offset = scroll_offset(self.selected_row(), list_rows_area(area).height);
         let list_item : Vec<ListItem> = items.iter()
-            .map(|i| {
+            .skip(offset)
+            .map(|(i, indices)| {
+                let mut spans = vec![];
                 if self.selected.contains(&i.id()) {
-                    ListItem::new(vec![Spans::from(Span::raw(format!(">> {}", i)))])
-                } else {
-                    ListItem::new(vec![Spans::from(Span::raw(format!("{}", i)))])
+                    spans.push(Span::raw(">> "));
                 }
+                spans.extend(highlight_matches(&i.to_string(), indices));
+                ListItem::new(vec![Spans::from(spans)])
             })
             .collect();
         let list_item = List::new(list_item)
-            .block(Block::default().borders(Borders::ALL))
+            .block(block)
             .highlight_style(Style::default().add_modifier(Modifier::BOLD).add_modifier(Modifier::UNDERLINED).add_modifier(Modifier::ITALIC));
-        f.render_stateful_widget(list_item, chunks[1], &mut self.state)
+        // Only the rows from the offset are passed in, so that clicks map
+        // to the same rows
+        let mut state = ListState::default();
+        state.select(self.selected_row().map(|i| i - offset));
+        StatefulWidget::render(list_item, chunks[1], buf, &mut state)
     }
 
-    fn key_event(&mut self, key: KeyEvent, mode: &AppMode) {
-        match mode {
-            AppMode::Search => {
-                self.state.select(Some(0));
-                match key.code {
-                    KeyCode::Char(c) => {
-                        self.search_text = format!("{}{}", self.search_text, c);
-                    }, 
-                    KeyCode::Backspace => {
-                        self.search_text.pop();
-                    }, 
-                   _ => {}
-                }
-            },
-            AppMode::Navigation => {
-                match key.code {
-                    KeyCode::Char(c) => {
-                        match c {
-                            'j' => self.next(),
-                            'k' => self.previous(),
-                            'c' => self.clear_selected(),
-                            _ => {}
-                        }
-                    },
-                    KeyCode::Up => {
-                        self.previous()
-                    }, 
-                    KeyCode::Down => {
-                        self.next()
+}
+
+impl<T: Display + Id + Clone> ListControl for StatefulList<T> {
+    fn next(&mut self) {
+        StatefulList::next(self)
+    }
+
+    fn previous(&mut self) {
+        StatefulList::previous(self)
+    }
+
+    fn toggle(&mut self) {
+        self.toggle_highlighted();
+        self.search_text = String::new();
+    }
+
+    fn clear_selected(&mut self) {
+        StatefulList::clear_selected(self)
+    }
+
+    fn search_push(&mut self, c: char) {
+        self.state.select(Some(0));
+        self.search_text.push(c);
+    }
+
+    fn search_pop(&mut self) {
+        self.state.select(Some(0));
+        self.search_text.pop();
+    }
+
+    fn mouse_event(&mut self, mouse: MouseEvent, area: Rect) -> Option<Action> {
+        if self.visible_items().is_empty() {
+            return None;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown => Some(Action::Next), 
+            MouseEventKind::ScrollUp => Some(Action::Previous), 
+            MouseEventKind::Down(MouseButton::Left) => {
+                let index = self.index_at(area, mouse.column, mouse.row)?;
+                self.state.select(Some(index));
+                let now = Instant::now();
+                match self.last_click {
+                    Some((i, t)) if i == index && now.duration_since(t) <= DOUBLE_CLICK => {
+                        self.last_click = None;
+                        Some(Action::Confirm)
                     }, 
-                    KeyCode::Enter => {
-                        self.toggle_highlighted();
-                        self.search_text = String::new();
+                    _ => {
+                        self.last_click = Some((index, now));
+                        None
                     }
-                    _ => {}
                 }
-            },
-            _ => {}
+            }, 
+            _ => None
+        }
+    }
+}
+
+// Title line and bordered list inside an area
+fn list_chunks(area: Rect) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .margin(5)
+        .constraints(
+            [
+            Constraint::Length(1), 
+            Constraint::Min(0),
+            ].as_ref()
+        ).split(area)
+}
+
+// First row shown when height rows fit, keeping the highlighted row in view
+pub fn scroll_offset(selected_row: Option<usize>, height: u16) -> usize {
+    let height = height as usize;
+    match selected_row {
+        Some(row) if height > 0 && row >= height => row + 1 - height, 
+        _ => 0, 
+    }
+}
+
+// Where the rows of a list rendered into an area are drawn
+pub fn list_rows_area(area: Rect) -> Rect {
+    Block::default().borders(Borders::ALL).inner(list_chunks(area)[1])
+}
+
+// Split text into spans, styling the chars at the matched indices
+fn highlight_matches(text: &str, indices: &[usize]) -> Vec<Span<'static>> {
+    let matched = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut spans = vec![];
+    let mut current = String::new();
+    let mut current_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_match = indices.binary_search(&i).is_ok();
+        if is_match != current_matched && !current.is_empty() {
+            let style = if current_matched { matched } else { Style::default() };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_matched = is_match;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        let style = if current_matched { matched } else { Style::default() };
+        spans.push(Span::styled(current, style));
+    }
+    spans
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterField {
+    Start, 
+    End, 
+    Project, 
+    Task, 
+    Tag, 
+    Billable, 
+    RunningOnly, 
+    Apply, 
+}
+
+impl FilterField {
+    pub const ALL: [FilterField; 8] = [
+        FilterField::Start, 
+        FilterField::End, 
+        FilterField::Project, 
+        FilterField::Task, 
+        FilterField::Tag, 
+        FilterField::Billable, 
+        FilterField::RunningOnly, 
+        FilterField::Apply, 
+    ];
+}
+
+impl fmt::Display for FilterField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterField::Start => write!(f, "Start date"), 
+            FilterField::End => write!(f, "End date"), 
+            FilterField::Project => write!(f, "Project"), 
+            FilterField::Task => write!(f, "Task"), 
+            FilterField::Tag => write!(f, "Tag"), 
+            FilterField::Billable => write!(f, "Billable"), 
+            FilterField::RunningOnly => write!(f, "Running only"), 
+            FilterField::Apply => write!(f, "Apply filters"), 
+        }
+    }
+}
+
+// Editing copy of the time entry filter. Dates are kept as typed until the
+// filter is applied.
+#[derive(Debug, Clone, Default)]
+pub struct FilterForm {
+    pub state: ListState, 
+    pub start: String, 
+    pub end: String, 
+    pub filter: TimeEntryFilter, 
+    pub error: Option<String>, 
+}
+
+impl From<&TimeEntryFilter> for FilterForm {
+    fn from(filter: &TimeEntryFilter) -> FilterForm {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        FilterForm {
+            state, 
+            start: filter.start.map(|d| d.to_string()).unwrap_or_default(), 
+            end: filter.end.map(|d| d.to_string()).unwrap_or_default(), 
+            filter: filter.clone(), 
+            error: None, 
         }
     }
 }
 
+impl FilterForm {
+    pub fn highlighted(&self) -> FilterField {
+        FilterField::ALL[self.state.selected().unwrap_or(0)]
+    }
+
+    fn parse_date(field: FilterField, text: &str) -> Result<Option<NaiveDate>, String> {
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| format!("{} must be formatted as YYYY-MM-DD", field))
+    }
+
+    // The filter to apply, or a message describing the invalid input
+    pub fn to_filter(&self) -> Result<TimeEntryFilter, String> {
+        let start = Self::parse_date(FilterField::Start, &self.start)?;
+        let end = Self::parse_date(FilterField::End, &self.end)?;
+        if let (Some(s), Some(e)) = (start, end) {
+            if e < s {
+                return Err(String::from("End date is before the start date"));
+            }
+        }
+        Ok(TimeEntryFilter { start, end, ..self.filter.clone() })
+    }
+
+    pub fn next(&mut self) {
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some((i + 1) % FilterField::ALL.len()));
+    }
+
+    pub fn previous(&mut self) {
+        let i = self.state.selected().unwrap_or(0);
+        self.state.select(Some((i + FilterField::ALL.len() - 1) % FilterField::ALL.len()));
+    }
+
+    // Reset every field, keeping the highlighted row
+    pub fn clear(&mut self) {
+        let i = self.state.selected();
+        *self = FilterForm::from(&TimeEntryFilter::default());
+        self.state.select(i.or(Some(0)));
+    }
+
+    // Text of the highlighted field if it is typed in
+    pub fn text_mut(&mut self) -> Option<&mut String> {
+        match self.highlighted() {
+            FilterField::Start => Some(&mut self.start), 
+            FilterField::End => Some(&mut self.end), 
+            _ => None, 
+        }
+    }
+
+    // Cycle the highlighted field if it takes fixed values
+    pub fn toggle(&mut self) {
+        match self.highlighted() {
+            FilterField::Billable => {
+                self.filter.billable = match self.filter.billable {
+                    None => Some(true), 
+                    Some(true) => Some(false), 
+                    Some(false) => None, 
+                };
+            }, 
+            FilterField::RunningOnly => {
+                self.filter.running_only = !self.filter.running_only;
+            }, 
+            _ => {}
+        }
+    }
+}
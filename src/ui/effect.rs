@@ -0,0 +1,255 @@
+use chrono::{prelude::*, Duration};
+use reqwest::blocking::Client;
+use crate::{
+    api::{
+        EndPoint,
+        EndpointError,
+        time_entry::{TimeEntry, TimeEntryFilter},
+        workspace::Workspace, project::Project, tag::Tag, task::Task,
+    },
+    clockify::{App, AppMode},
+    error::Error,
+    local_config,
+    time_parser::TimeExpr,
+    ui::{
+        components::{StatefulList, Id},
+        grid::{GridRow, week_containing},
+        screen::describe_time,
+        ScreenId,
+    },
+};
+
+// Requests returned by update(), run by perform() once the state is updated.
+// Loads only fetch a list when it is empty, unless forced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    // Everything shown at startup
+    Load,
+    LoadWorkspaces { force: bool },
+    LoadProjects { force: bool },
+    // Tasks of the selected project, or of the first one
+    LoadTasks { force: bool },
+    LoadTags { force: bool },
+    LoadTimeEntries { force: bool },
+    LoadGrid { force: bool },
+    LoadUser,
+    // The entry shown on the Home screen
+    LoadCurrentEntry,
+    StartTimer,
+    StartAt(TimeExpr),
+    StopTimer,
+    UpdateEntry,
+    // Start a loaded time entry again, by id
+    ContinueEntry(String),
+    ValidateApiKey(String),
+    // Save the key and workspace picked on the first run
+    FinishOnboarding,
+    SwitchProfile(String),
+    SetDuration(GridRow, NaiveDate, Duration),
+    Poll,
+}
+
+// Run an effect and keep the entry shown on the Home screen up to date.
+// Failures the user can correct become messages, others are returned.
+pub fn perform(client: &Client, app: &mut App, effect: Effect) -> Result<(), Error> {
+    match effect {
+        Effect::Load => load(client, app)?,
+        Effect::LoadWorkspaces { force } => refresh_workspaces(client, app, force)?,
+        Effect::LoadProjects { force } => refresh_projects(client, app, force)?,
+        Effect::LoadTasks { force } => {
+            // Ensure that a project is set in the config
+            refresh_projects(client, app, false)?;
+            if let Some(project_id) = app.projects.get_selected_item() {
+                app.config.project_id = Some(project_id.clone().id());
+            } else {
+                app.config.project_id = Some(app.projects.items.first().ok_or(Error::MissingData)?.clone().id());
+            }
+            refresh_tasks(client, app, force)?;
+        },
+        Effect::LoadTags { force } => refresh_tags(client, app, force)?,
+        Effect::LoadTimeEntries { force } => refresh_time_entries(client, app, force)?,
+        Effect::LoadGrid { force } => refresh_grid(client, app, force)?,
+        Effect::LoadUser => { app.current_user(client)?; },
+        Effect::LoadCurrentEntry => app.current_entry = app.get_current_entry(client)?,
+        Effect::StartTimer => app.start_entry(client)?,
+        Effect::StartAt(time) => {
+            app.start_entry_at(client, time)?;
+            app.message = Some(describe_time(&app.formatter, &time));
+            app.time_input.text = String::new();
+            app.current_mode = AppMode::Navigation;
+            app.navigate(ScreenId::Home);
+        },
+        Effect::StopTimer => {
+            app.message = Some(match app.stop_entry(client)? {
+                Some(time_entry) => format!("Stopped: {}", time_entry),
+                None => String::from("No timer is running"),
+            });
+            app.update_state(client);
+            refresh_time_entries(client, app, true)?;
+        },
+        Effect::UpdateEntry => {
+            app.update_entry(client)?;
+            app.current_entry = app.get_current_entry(client)?;
+        },
+        Effect::ContinueEntry(id) => {
+            let time_entry = app.time_entries.get_by_id(id).ok_or(Error::MissingTimeEntry)?.clone();
+            app.continue_entry(client, &time_entry)?;
+            refresh_time_entries(client, app, true)?;
+            app.navigate(ScreenId::Home);
+        },
+        Effect::ValidateApiKey(api_key) => match app.validate_api_key(client, &api_key) {
+            Ok(user) => {
+                app.message = Some(format!(
+                    "Signed in as {} <{}>, select a default workspace",
+                    user.name.unwrap_or_default(),
+                    user.email
+                ));
+                app.api_key_input.text = String::new();
+                app.current_mode = AppMode::Navigation;
+                app.navigate(ScreenId::WorkspaceSelection);
+                refresh_workspaces(client, app, false)?;
+            },
+            Err(Error::Api(EndpointError::Unauthorized)) => {
+                app.message = Some(String::from("The API key was rejected, check it and try again"));
+            },
+            Err(e) => {
+                app.message = Some(format!("Could not check the API key: {}", e));
+            },
+        },
+        Effect::FinishOnboarding => {
+            app.store_config()?;
+            app.onboarding = false;
+            app.navigate(ScreenId::Loading);
+            // Signing in starts a new history
+            app.history.clear();
+            load(client, app)?;
+        },
+        Effect::SwitchProfile(name) => {
+            app.switch_profile(&name);
+            // A profile with an API key starts over from the Loading screen
+            if app.current_screen == ScreenId::Loading {
+                load(client, app)?;
+            }
+        },
+        Effect::SetDuration(row, day, total) => match app.set_duration_for_day(client, &row, day, total) {
+            Ok(()) => refresh_grid(client, app, true)?,
+            Err(Error::RunningTimeEntry) => {
+                app.grid.message = Some(String::from("Stop the running timer before changing this day"));
+            },
+            Err(e) => return Err(e),
+        },
+        Effect::Poll => app.on_tick(client),
+    }
+    // The timer was started, stopped or switched
+    if app.current_entry.as_ref().and_then(|e| e.id.as_ref()) != app.current_entry_id.as_ref() {
+        app.current_entry = app.get_current_entry(client)?;
+    }
+    Ok(())
+}
+
+// Refresh workspaces
+fn refresh_workspaces(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
+    if app.workspaces.items.is_empty() || force {
+            app.workspaces = StatefulList::with_items(Workspace::list(client, &app.config, None)?, String::from("Select a workspace: "), false);
+    }
+    Ok(())
+}
+
+// Refresh projects
+fn refresh_projects(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
+    if app.projects.items.is_empty() || force {
+            app.projects = StatefulList::with_items(Project::list(client, &app.config, None)?, String::from("Select a project: "), false);
+            app.update_name_cache();
+    }
+    Ok(())
+}
+
+// Refresh tasks
+fn refresh_tasks(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
+    if app.tasks.items.is_empty() || force {
+        app.tasks = StatefulList::with_items(Task::list(client, &app.config, None)?, String::from("Select a task: "), false);
+        app.update_name_cache();
+    }
+    Ok(())
+}
+
+// Refresh tags
+fn refresh_tags(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
+    if app.tags.items.is_empty() || force {
+        app.tags = StatefulList::with_items(Tag::list(client, &app.config, None)?, String::from("Select a tag: "), true);
+        app.update_name_cache();
+    }
+    Ok(())
+}
+
+// Refresh Time Entries
+fn refresh_time_entries(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
+    // A filtered list may legitimately be empty, so it is only reloaded on request
+    if (app.time_entries.items.is_empty() && app.time_entry_filter.is_empty()) || force {
+        // Narrow the request where the API supports it, then apply every filter locally
+        let filter = app.time_entry_filter.clone();
+        // Hydrated entries name their task even when it is not in app.tasks
+        let params = filter.to_params(&app.formatter).with("hydrated", true);
+        let time_entries = TimeEntry::list(client, &app.config, Some(params))?
+            .into_iter()
+            .filter(|entry| filter.matches(entry, &app.formatter))
+            .collect::<Vec<TimeEntry>>();
+        app.time_entries = StatefulList::with_items(time_entries, String::from("Select a time entry: "), false);
+    }
+    Ok(())
+}
+
+// Weekly Grid
+fn refresh_grid(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
+    if app.grid.week_start.is_none() {
+        // The formatter follows the user's settings once they are loaded
+        app.current_user(client)?;
+        app.grid.set_week(week_containing(app.formatter.today(), app.formatter.week_start));
+    }
+    if app.grid.entries.is_none() || force {
+        let days = app.grid.days();
+        let filter = TimeEntryFilter { start: days.first().copied(), end: days.last().copied(), ..Default::default() };
+        let params = filter.to_params(&app.formatter)
+            .with("hydrated", true)
+            .with("page-size", 1000);
+        app.grid.entries = Some(TimeEntry::list(client, &app.config, Some(params))?);
+    }
+    // The selected project and task always get a row
+    let selection = app.projects.get_selected_item().map(|project| GridRow {
+        project_id: Some(project.id()),
+        task_id: app.tasks.get_selected_item().map(|task| task.id()),
+    });
+    app.grid.update_rows(selection);
+    Ok(())
+}
+
+// Loading
+fn load(client: &Client, app: &mut App) -> Result<(), Error> {
+    if app.config.workspace_id.is_some() {
+        refresh_tags(client, app, true)?;
+        refresh_projects(client, app, true)?;
+    }
+
+    if app.config.project_id.is_some() {
+        refresh_tasks(client, app, true)?;
+    }
+    // Set Current User, whose settings are used to show times
+    app.current_user(client)?;
+    if app.config.user_id.is_some() {
+        refresh_time_entries(client, app, true)?;
+    }
+    refresh_workspaces(client, app, true)?;
+    if app.config.workspace_id.is_none() {
+        app.navigate(ScreenId::WorkspaceSelection);
+    } else {
+        // Defaults of a .clockify.toml and the git branch
+        if let Err(e) = app.apply_local_config(client) {
+            app.message = Some(format!("Could not apply {}: {}", local_config::FILE_NAME, e));
+        }
+        if let Err(e) = app.apply_git_branch(client) {
+            app.message = Some(format!("Could not apply the git branch: {}", e));
+        }
+        app.navigate(ScreenId::Home);
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+// Fuzzy subsequence matching used to search lists.
+//
+// Every character of the pattern has to appear in the text in order. Matches
+// score higher when they are consecutive or start a word, and lose a point for
+// every character skipped between two matches.
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_BOUNDARY: i64 = 10;
+const PENALTY_GAP: i64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    // Char indices into the text that matched the pattern
+    pub indices: Vec<usize>,
+}
+
+fn same_char(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn is_boundary(text: &[char], j: usize) -> bool {
+    if j == 0 {
+        return true;
+    }
+    let prev = text[j - 1];
+    let current = text[j];
+    !prev.is_alphanumeric() || (prev.is_lowercase() && current.is_uppercase())
+}
+
+// Best match of a single word (no whitespace) in text
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch { score: 0, indices: vec![] });
+    }
+    if pattern.len() > text.len() {
+        return None;
+    }
+
+    // scores[i][j]: best score with pattern[i] matched at text[j]
+    // from[i][j]: position of pattern[i - 1] on that best path
+    let mut scores: Vec<Vec<Option<i64>>> = vec![vec![None; text.len()]; pattern.len()];
+    let mut from: Vec<Vec<usize>> = vec![vec![0; text.len()]; pattern.len()];
+    for (i, p) in pattern.iter().enumerate() {
+        // Best (score + position) of the previous row before j - 1, so the gap
+        // penalty can be applied without rescanning the row.
+        let mut best_gapped: Option<(i64, usize)> = None;
+        for j in 0..text.len() {
+            if i > 0 && j >= 2 {
+                if let Some(s) = scores[i - 1][j - 2] {
+                    let candidate = s + (j - 2) as i64 * PENALTY_GAP;
+                    if best_gapped.is_none_or(|(b, _)| candidate > b) {
+                        best_gapped = Some((candidate, j - 2));
+                    }
+                }
+            }
+            if !same_char(*p, text[j]) {
+                continue;
+            }
+            let mut score = SCORE_MATCH;
+            if is_boundary(&text, j) {
+                score += BONUS_BOUNDARY;
+            }
+            if i == 0 {
+                scores[i][j] = Some(score);
+                continue;
+            }
+            let consecutive = if j >= 1 {
+                scores[i - 1][j - 1].map(|s| (s + BONUS_CONSECUTIVE, j - 1))
+            } else {
+                None
+            };
+            let gapped = best_gapped.map(|(b, k)| (b - (j - 1) as i64 * PENALTY_GAP, k));
+            let best = match (consecutive, gapped) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+            if let Some((prev_score, k)) = best {
+                scores[i][j] = Some(prev_score + score);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    // Pick the best end position and walk back to recover the matched indices
+    let last = pattern.len() - 1;
+    let (mut j, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .fold(None, |best: Option<(usize, i64)>, (j, s)| match best {
+            Some((_, b)) if b >= s => best,
+            _ => Some((j, s)),
+        })?;
+    let mut indices = vec![j; pattern.len()];
+    for i in (1..pattern.len()).rev() {
+        j = from[i][j];
+        indices[i - 1] = j;
+    }
+    Some(FuzzyMatch { score, indices })
+}
+
+// Every whitespace separated word of the query has to match
+pub fn fuzzy_search(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let mut score = 0;
+    let mut indices = vec![];
+    for word in query.split_whitespace() {
+        let m = fuzzy_match(word, text)?;
+        score += m.score;
+        indices.extend(m.indices);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Some(FuzzyMatch { score, indices })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::tag::Tag, ui::components::StatefulList};
+
+    fn score(pattern: &str, text: &str) -> i64 {
+        fuzzy_match(pattern, text).unwrap().score
+    }
+
+    #[test]
+    fn subsequence() {
+        assert_eq!(fuzzy_match("dsgn", "Design").unwrap().indices, [0, 2, 4, 5]);
+        assert!(fuzzy_match("DESIGN", "design").is_some());
+        assert!(fuzzy_match("ndsg", "Design").is_none());
+        assert!(fuzzy_match("designs", "Design").is_none());
+        assert_eq!(fuzzy_match("", "Design"), Some(FuzzyMatch { score: 0, indices: vec![] }));
+    }
+
+    #[test]
+    fn ranking() {
+        // Word starts beat the middle of a word, which beats scattered letters
+        assert!(score("des", "Design") > score("des", "Modes"));
+        assert!(score("des", "Modes") > score("des", "Docs released"));
+        // Larger gaps cost more
+        assert!(score("dr", "Docs review") > score("dr", "Docs are reviewed"));
+        // The best of several positions is kept
+        assert_eq!(fuzzy_match("re", "pre review").unwrap().indices, [4, 5]);
+    }
+
+    #[test]
+    fn ties_go_to_shorter_text() {
+        let tags = vec![Tag::from("Design review"), Tag::from("Modes"), Tag::from("Design")];
+        let list = StatefulList::with_items(tags, String::new(), false);
+        let names: Vec<String> = list.search("design").iter().map(|t| t.to_string()).collect();
+        assert_eq!(names, ["Design", "Design review"]);
+        let names: Vec<String> = list.search("des").iter().map(|t| t.to_string()).collect();
+        assert_eq!(names, ["Design", "Design review", "Modes"]);
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let m = fuzzy_search("page web", "Landing page for website").unwrap();
+        assert_eq!(m.score, score("page", "Landing page for website") + score("web", "Landing page for website"));
+        assert!(fuzzy_search("page blog", "Landing page for website").is_none());
+        assert_eq!(fuzzy_search("  ", "Landing page"), Some(FuzzyMatch { score: 0, indices: vec![] }));
+    }
+
+    #[test]
+    fn highlighted_indices() {
+        // Sorted and without duplicates, as highlight_matches expects
+        assert_eq!(fuzzy_search("lp", "Landing page").unwrap().indices, [0, 8]);
+        assert_eq!(fuzzy_search("page pa", "Landing page").unwrap().indices, [8, 9, 10, 11]);
+        assert_eq!(fuzzy_search("web page", "Landing page for website").unwrap().indices, [8, 9, 10, 11, 17, 18, 19]);
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{prelude::*, Duration};
+
+use crate::{api::time_entry::TimeEntry, format::Formatter};
+
+// A project/task combination shown as one row of the weekly grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridRow {
+    pub project_id: Option<String>,
+    pub task_id: Option<String>,
+}
+
+impl GridRow {
+    pub fn contains(&self, entry: &TimeEntry) -> bool {
+        entry.project_id == self.project_id && entry.task_id == self.task_id
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WeeklyGrid {
+    // First day of the week shown, set when the screen is first opened
+    pub week_start: Option<NaiveDate>,
+    // Entries of the week, None until loaded
+    pub entries: Option<Vec<TimeEntry>>,
+    pub rows: Vec<GridRow>,
+    pub row: usize,
+    pub column: usize,
+    pub input: String,
+    pub message: Option<String>,
+}
+
+impl WeeklyGrid {
+    pub fn days(&self) -> Vec<NaiveDate> {
+        match self.week_start {
+            Some(start) => (0..7).map(|i| start + Duration::days(i)).collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn selected_row(&self) -> Option<&GridRow> {
+        self.rows.get(self.row)
+    }
+
+    pub fn selected_day(&self) -> Option<NaiveDate> {
+        self.days().get(self.column).copied()
+    }
+
+    pub fn set_week(&mut self, week_start: NaiveDate) {
+        self.week_start = Some(week_start);
+        self.entries = None;
+    }
+
+    // Entries of a cell, earliest first. Days are in the formatter's zone.
+    pub fn cell_entries(&self, row: &GridRow, day: NaiveDate, formatter: &Formatter) -> Vec<TimeEntry> {
+        let mut entries = self.entries
+            .iter()
+            .flatten()
+            .filter(|entry| row.contains(entry) && local_date(entry, formatter) == Some(day))
+            .cloned()
+            .collect::<Vec<TimeEntry>>();
+        entries.sort_by_key(|entry| entry.start_time());
+        entries
+    }
+
+    pub fn cell_total(&self, row: &GridRow, day: NaiveDate, formatter: &Formatter) -> Duration {
+        self.cell_entries(row, day, formatter)
+            .iter()
+            .filter_map(|entry| entry.duration())
+            .fold(Duration::zero(), |total, d| total + d)
+    }
+
+    pub fn day_total(&self, day: NaiveDate, formatter: &Formatter) -> Duration {
+        self.rows
+            .iter()
+            .map(|row| self.cell_total(row, day, formatter))
+            .fold(Duration::zero(), |total, d| total + d)
+    }
+
+    // Latest end of any entry that day, so new entries can be placed after it
+    pub fn day_end(&self, day: NaiveDate, formatter: &Formatter) -> Option<DateTime<Utc>> {
+        self.entries
+            .iter()
+            .flatten()
+            .filter(|entry| local_date(entry, formatter) == Some(day))
+            .filter_map(|entry| entry.end_time())
+            .max()
+    }
+
+    // Rows for every project/task tracked this week, plus an extra one such as
+    // the current selection so time can be added to it.
+    pub fn update_rows(&mut self, extra: Option<GridRow>) {
+        let mut rows: Vec<GridRow> = vec![];
+        for entry in self.entries.iter().flatten() {
+            let row = GridRow { project_id: entry.project_id.clone(), task_id: entry.task_id.clone() };
+            if !rows.contains(&row) {
+                rows.push(row);
+            }
+        }
+        if let Some(row) = extra {
+            if !rows.contains(&row) {
+                rows.push(row);
+            }
+        }
+        self.rows = rows;
+        if self.row >= self.rows.len() {
+            self.row = self.rows.len().saturating_sub(1);
+        }
+    }
+}
+
+fn local_date(entry: &TimeEntry, formatter: &Formatter) -> Option<NaiveDate> {
+    entry.start_time().map(|s| formatter.local_date(s))
+}
+
+// First day of the week containing date
+pub fn week_containing(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let days = (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+    date - Duration::days(days as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn week_containing_each_start() {
+        // Wednesday
+        let date = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        let starts = [
+            (Weekday::Mon, 11),
+            (Weekday::Tue, 12),
+            (Weekday::Wed, 13),
+            (Weekday::Thu, 7),
+            (Weekday::Fri, 8),
+            (Weekday::Sat, 9),
+            (Weekday::Sun, 10),
+        ];
+        for (week_start, day) in starts {
+            let start = week_containing(date, week_start);
+            assert_eq!(start, NaiveDate::from_ymd_opt(2024, 3, day).unwrap(), "{}", week_start);
+            assert_eq!(start.weekday(), week_start);
+        }
+        // The first day of a week is its own start
+        let sunday = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(week_containing(sunday, Weekday::Sun), sunday);
+        assert_eq!(week_containing(sunday, Weekday::Mon), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+    }
+}
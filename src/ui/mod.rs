@@ -1,8 +1,13 @@
+pub mod action;
 pub mod components; 
+pub mod effect;
+pub mod fuzzy;
+pub mod grid;
 pub mod screen;
+pub mod update;
 
 use crossterm::{
-    event::{self, EnableMouseCapture, Event, DisableMouseCapture}, 
+    event::{self, EnableMouseCapture, Event, DisableMouseCapture, KeyEvent}, 
     execute, 
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}
 }; 
@@ -17,29 +22,37 @@ use tui::{
 use reqwest::blocking::Client; 
 use crate::{
     clockify::App,
-    // ui::components::Component, 
-    error::Error
+    error::Error, 
+    ui::action::Action, 
 };
 
  
 
-#[derive(Debug, Clone)]
-pub enum Screen {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenId {
     Loading, 
     Home,
     WorkspaceSelection, 
     TimeEntrySelection, 
+    TimeEntryFilter, 
     ProjectSelection,
     TaskSelection,
     TagSelection,
     DescriptionEdit, 
+    WeeklyGrid, 
+    ProfileSelection, 
+    Onboarding, 
+    TimeInput, 
 }
 
 pub fn run(app: &mut App, tick_rate: Duration) -> Result<(), Error> {
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?; 
+    execute!(stdout, EnterAlternateScreen)?; 
+    if app.config.mouse_capture {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?; 
 
@@ -59,52 +72,25 @@ pub fn run(app: &mut App, tick_rate: Duration) -> Result<(), Error> {
 }
 
 pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, client: &Client, app: &mut App, tick_rate: Duration) -> Result<(), Error> {
+    // Show the Loading screen while the first screen is loaded
+    draw(terminal, app)?;
+    dispatch(client, app, Action::Show(app.current_screen))?;
     let mut last_tick = Instant::now();
     loop {
-        terminal.draw(|f| {
-            let res = match app.current_screen {
-                Screen::Loading => screen::loading(f, client, app, None), 
-                Screen::Home => screen::home(f, client, app, None),
-                Screen::WorkspaceSelection => screen::workspace_selection(f, client, app, None),
-                Screen::TimeEntrySelection => screen::time_entry_selection(f, client, app, None),
-                Screen::ProjectSelection => screen::project_selection(f, client, app, None),
-                Screen::TaskSelection => screen::task_selection(f, client, app, None),
-                Screen::TagSelection => screen::tag_selection(f, client, app, None), 
-                Screen::DescriptionEdit => screen::description_input(f, client, app, None), 
-            };
-            if let Err(e) = res {
-                app.error = Some(e);
-                app.should_quit = true;
-            }
-        })?;
+        draw(terminal, app)?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                // Screen specific key event
-                terminal.draw(|f| {
-                   let res : Result<(), Error> = match app.current_screen {
-                        Screen::WorkspaceSelection => screen::workspace_selection(f, client, app, Some(key)), 
-                        Screen::TimeEntrySelection => screen::time_entry_selection(f, client, app, Some(key)), 
-                        Screen::ProjectSelection => screen::project_selection(f, client, app, Some(key)),
-                        Screen::TaskSelection => screen::task_selection(f, client, app, Some(key)),
-                        Screen::TagSelection => screen::tag_selection(f, client, app, Some(key)),
-                        Screen::DescriptionEdit => screen::description_input(f, client, app, Some(key)), 
-                        _ => Ok(())
-                    };
-                   if let Err(e) = res {
-                       app.error = Some(e);
-                       app.should_quit = true;
-                   }
-                })?; 
-                // App key events
-                app.key_event(key, client)?
+            match event::read()? {
+                Event::Key(key) => handle_key(client, app, key)?, 
+                Event::Mouse(mouse) => dispatch(client, app, Action::Mouse(mouse, terminal.size()?))?, 
+                _ => {}
             }
         }
         if last_tick.elapsed() >= tick_rate {
-            // app.on_tick();
+            dispatch(client, app, Action::Tick)?;
             last_tick = Instant::now();
         }
         if app.should_quit {
@@ -114,3 +100,28 @@ pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, client: &Client, app: &mu
   
 }
 
+// Render the current screen, without loading or changing anything
+pub fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &App) -> Result<(), Error> {
+    terminal.draw(|f| {
+        let area = f.size();
+        f.render_widget(screen::View(app), area);
+    })?;
+    Ok(())
+}
+
+// Apply the action of a key, if it has one
+pub fn handle_key(client: &Client, app: &mut App, key: KeyEvent) -> Result<(), Error> {
+    app.message = None;
+    match action::from_key(app, key) {
+        Some(action) => dispatch(client, app, action), 
+        None => Ok(()), 
+    }
+}
+
+// Update the state, then make the requests it calls for
+pub fn dispatch(client: &Client, app: &mut App, action: Action) -> Result<(), Error> {
+    for effect in update::update(app, action) {
+        effect::perform(client, app, effect)?;
+    }
+    Ok(())
+}
@@ -1,452 +1,1123 @@
-use std::collections::HashMap;
+use chrono::{prelude::*, Duration};
 
-use crate::error::Error;
-
-use crossterm::{
-    event::{KeyCode, KeyEvent}
-};
+use crossterm::event::KeyCode;
 use tui::{
-    Frame,
-    backend::Backend,
+    buffer::Buffer,
     layout::{Constraint, Layout, Rect},
-    widgets::{Paragraph, Table, Row, TableState, Block},
+    widgets::{Paragraph, Table, Row, Cell, TableState, Block, Borders, List, ListItem, StatefulWidget, Widget},
     style::{Modifier, Style},
 };
-use reqwest::blocking::Client;
 use crate::{
-    clockify::App,
+    clockify::{App, AppMode},
+    error::Error,
+    format::{format_duration, Formatter},
+    time_parser::{parse_duration, TimeExpr},
     api::{
-        EndPoint,
-        user::User, 
         time_entry::TimeEntry, 
-        workspace::Workspace, project::Project, tag::Tag, task::Task, 
-        EndpointParameters,
-        ParameterValue,
+        project::Project, tag::Tag, task::Task, 
     }, 
     ui::{
-        components::{StatefulList, Component, Id}, 
-        Screen
+        action::Action, 
+        components::{Component, FilterField, FilterForm, Id, ListControl, list_rows_area, scroll_offset}, 
+        effect::Effect, 
+        grid::{GridRow, week_containing}, 
+        update::show, 
+        ScreenId
     } 
 };
 
+// One screen of the app. Its state is kept on App, the screen draws it and
+// applies the actions meant for it. A new screen only needs an implementation
+// and an entry in ScreenId::screen().
+pub trait Screen {
+    // Shown after the app title
+    fn title(&self) -> &'static str;
 
-// Template chunks
-fn template_screen<B: Backend>(f: &mut Frame<B>, _client: &Client, _app: &mut App) -> Vec<Rect> {
-    let chunks = Layout::default()
-        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
-        .split(f.size());
-    chunks 
-    
-}
+    // Keys of the screen in Navigation mode, looked up before the global ones
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &[]
+    }
+
+    // Whether the global keys (other screens, timer, modes) work here
+    fn global_keys(&self) -> bool {
+        true
+    }
+
+    // The list moved by Next, Previous, Clear, searching and the mouse
+    fn list<'b>(&self, _app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        None
+    }
+
+    // Loads needed by the screen when it is shown
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![]
+    }
+
+    // Apply an action, or leave it to the global handling by returning None
+    fn handle_action(&self, _app: &mut App, _action: &Action) -> Option<Vec<Effect>> {
+        None
+    }
 
-// Refresh workspaces
-fn refresh_workspaces(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
-    if app.workspaces.items.is_empty() || force {
-            app.workspaces = StatefulList::with_items(Workspace::list(client, &app.config, None)?, String::from("Select a workspace: "), false);
+    // Where the rows of the list are drawn in the body of the screen, for
+    // mouse clicks
+    fn list_area(&self, area: Rect) -> Rect {
+        list_rows_area(area)
     }
-    Ok(())
+
+    // Update the rows of the list to what is shown, before each action and
+    // never while drawing
+    fn refresh_rows(&self, _app: &mut App) {}
+
+    // Draw the body of the screen, below the title and status message
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer);
 }
 
-// Refresh projects
-fn refresh_projects(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
-    if app.projects.items.is_empty() || force {
-            app.projects = StatefulList::with_items(Project::list(client, &app.config, None)?, String::from("Select a project: "), false);
+impl ScreenId {
+    pub fn screen(&self) -> &'static dyn Screen {
+        match self {
+            ScreenId::Loading => &Loading, 
+            ScreenId::Home => &Home, 
+            ScreenId::WorkspaceSelection => &WorkspaceSelection, 
+            ScreenId::TimeEntrySelection => &TimeEntrySelection, 
+            ScreenId::TimeEntryFilter => &TimeEntryFilter, 
+            ScreenId::ProjectSelection => &ProjectSelection, 
+            ScreenId::TaskSelection => &TaskSelection, 
+            ScreenId::TagSelection => &TagSelection, 
+            ScreenId::DescriptionEdit => &DescriptionEdit, 
+            ScreenId::WeeklyGrid => &WeeklyGrid, 
+            ScreenId::ProfileSelection => &ProfileSelection, 
+            ScreenId::Onboarding => &Onboarding, 
+            ScreenId::TimeInput => &TimeInput, 
+        }
     }
-    Ok(())
 }
 
-// Refresh tasks
-fn refresh_tasks(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
-    if app.tasks.items.is_empty() || force {
-        app.tasks = StatefulList::with_items(Task::list(client, &app.config, None)?, String::from("Select a task: "), false);
+// Title and body of every screen
+fn screen_chunks(area: Rect) -> Vec<Rect> {
+    Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area)
+}
+
+// Where the rows of the current screen's list are drawn in the whole terminal
+pub fn list_area(app: &App, area: Rect) -> Rect {
+    app.current_screen.screen().list_area(screen_chunks(area)[1])
+}
+
+// The current screen below the app title and the status message
+pub struct View<'a, 'b>(pub &'a App<'b>);
+
+impl<'a, 'b> Widget for View<'a, 'b> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let app = self.0;
+        let screen = app.current_screen.screen();
+        let chunks = screen_chunks(area);
+        Paragraph::new(format!("{} - {}", app, screen.title())).render(chunks[0], buf);
+        // Status message on the line below the title
+        if let Some(message) = &app.message {
+            let area = Rect { y: chunks[0].y + 1, height: 1, ..chunks[0] };
+            Paragraph::new(message.clone()).render(area, buf);
+        }
+        screen.render(app, chunks[1], buf);
     }
-    Ok(())
 }
 
-// Refresh tags
-fn refresh_tags(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
-    if app.tags.items.is_empty() || force {
-        app.tags = StatefulList::with_items(Tag::list(client, &app.config, None)?, String::from("Select a tag: "), true);
+// Typing into a text field in Edit mode
+fn edit_text(text: &mut String, action: &Action) -> Option<Vec<Effect>> {
+    match action {
+        Action::Type(c) => text.push(*c), 
+        Action::Backspace => { text.pop(); }, 
+        _ => return None, 
     }
-    Ok(())
+    Some(vec![])
 }
 
-// Refresh Time Entries
-fn refresh_time_entries(client: &Client, app: &mut App, force: bool) -> Result<(), Error> {
-    if app.time_entries.items.is_empty() || force {
-        app.time_entries = StatefulList::with_items(TimeEntry::list(client, &app.config, None)?, String::from("Select a time entry: "), false);
+// Keys moving the highlight of a list, form or grid
+const MOVE_KEYS: [(KeyCode, Action); 4] = [
+    (KeyCode::Char('j'), Action::Next), 
+    (KeyCode::Down, Action::Next), 
+    (KeyCode::Char('k'), Action::Previous), 
+    (KeyCode::Up, Action::Previous), 
+];
+
+// Keys of the screens showing a list
+const LIST_KEYS: [(KeyCode, Action); 7] = keys(MOVE_KEYS, [
+    (KeyCode::Char('c'), Action::Clear), 
+    (KeyCode::Char('r'), Action::Refresh), 
+    (KeyCode::Enter, Action::Confirm), 
+]);
+
+// Keys of the screens with a text field
+const INPUT_KEYS: [(KeyCode, Action); 2] = [
+    (KeyCode::Char('c'), Action::Clear), 
+    (KeyCode::Enter, Action::Confirm), 
+];
+
+// Shared keys followed by the keys of a screen, so that the shared ones are
+// defined once
+const fn keys<const A: usize, const B: usize, const N: usize>(
+    shared: [(KeyCode, Action); A], 
+    own: [(KeyCode, Action); B], 
+) -> [(KeyCode, Action); N] {
+    assert!(A + B == N, "the keymap has A + B keys");
+    let mut keys = [(KeyCode::Null, Action::Tick); N];
+    let mut i = 0;
+    while i < N {
+        keys[i] = if i < A { shared[i] } else { own[i - A] };
+        i += 1;
     }
-    Ok(())
+    keys
 }
 
 // Loading
-pub fn loading<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, _key: Option<KeyEvent>) -> Result<(), Error> {
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]);
-    f.render_widget(Paragraph::new("Loading Data"), chunks[1]);
-    if app.config.workspace_id.is_some() {
-        refresh_tags(client, app, true)?;
-        refresh_projects(client, app, true)?;
-    }
+pub struct Loading;
 
-    if app.config.project_id.is_some() {
-        refresh_tasks(client, app, true)?;
+impl Screen for Loading {
+    fn title(&self) -> &'static str {
+        "Loading"
     }
-    // Set Current User
 
-    if app.config.user_id.is_some() {
-        refresh_time_entries(client, app, true)?;
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![Effect::Load]
     }
-    refresh_workspaces(client, app, true)?;
-    if app.config.workspace_id.is_none() {
-        app.current_screen = Screen::WorkspaceSelection;
-    } else {
-        app.current_screen = Screen::Home;
+
+    fn render(&self, _app: &App, area: Rect, buf: &mut Buffer) {
+        Paragraph::new("Loading Data").render(area, buf);
     }
-    Ok(())
 }
 
 // Home
-pub fn home<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, _key: Option<KeyEvent>) -> Result<(), Error>{
-     // Force workspace selection
-    if app.config.workspace_id.is_none() {
-        app.current_screen = Screen::WorkspaceSelection;
-        return Ok(())
-    }
-    // If no user_id, send request
-    if app.config.user_id.is_none() {
-        let current_user = client.get(format!("{}{}", app.config.base_url, "/user"))
-            .header("X-API-KEY", app.config.api_key.as_ref().ok_or(Error::MissingApiKey)?.clone())
-            .send()?
-            .json::<User>()?;
-        app.config.user_id = current_user.id;
-    }
-
-    // Check if there is a currently running entry
-    if app.current_entry_id.is_none() {
-        // Only get first record from list
-        let mut params : EndpointParameters = HashMap::new();
-        params.insert("page-size".to_owned(), ParameterValue::from(1)); 
-        let time_entries : Vec<TimeEntry> = TimeEntry::list(client, &app.config, Some(params))?;
-        if let Some(time_entry) = time_entries.get(0) {
+pub struct Home;
+
+impl Screen for Home {
+    fn title(&self) -> &'static str {
+        "Home"
+    }
+
+    fn on_show(&self, app: &mut App) -> Vec<Effect> {
+        // Force workspace selection
+        if app.config.workspace_id.is_none() {
+            app.redirect(ScreenId::WorkspaceSelection);
+            return WorkspaceSelection.on_show(app);
+        }
+        // If no user_id, send request
+        if app.config.user_id.is_none() {
+            return vec![Effect::LoadUser, Effect::LoadCurrentEntry];
+        }
+        vec![Effect::LoadCurrentEntry]
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        // Display current time entry
+        let current_entry_chunks = Layout::default()
+            .constraints([
+                Constraint::Length(1), // Description
+                Constraint::Length(1), // Task
+                Constraint::Length(1), // Tag
+                Constraint::Length(1), // Project
+                Constraint::Length(1), // Start
+                Constraint::Length(1), // Stop
+            ].as_ref())
+            .split(area);
+        // Description
+        Paragraph::new(format!("{}: {}", "Description", app.description.text.clone())).render(current_entry_chunks[0], buf); 
+
+        // Task
+        let task: Option<&Task> = app.tasks.get_selected_item();
+        let task_text : String = match task {
+            Some(t) => t.name.clone(), 
+            None => String::from("")
+        };
+        Paragraph::new(format!("{}: {}", "Task", task_text)).render(current_entry_chunks[1], buf);
+        // Tag
+        let tags: Vec<&Tag> = app.tags.get_selected_items();
+        let tag_string = tags.iter().map(|x| x.to_string() + ", ").collect::<String>();
+        let tag_string = tag_string.trim_end_matches(", ");
+        Paragraph::new(format!("{}: {}", "Tag", tag_string)).render(current_entry_chunks[2], buf); 
+        // Project
+        let project: Option<&Project> = app.projects.get_selected_item();
+        let project_text : String = match project {
+            Some(project) => project.name.clone(), 
+            None => String::from("")
+        };
+        Paragraph::new(format!("{}: {}", "Project", project_text)).render(current_entry_chunks[3], buf); 
+        //Time Entries
+        if let Some(current_time) = &app.current_entry {
+            // Start
+            if let Some(start) = current_time.start_time() {
+                Paragraph::new(format!("{}: {}", "Start", app.formatter.date_time(start))).render(current_entry_chunks[4], buf); 
+            }
             // End
-            let end : Option<String> = time_entry.clone().time_interval.ok_or(Error::MissingData)?.end;
-            if end.is_none() {
-                // Change current project
-                app.current_entry_id = Some(time_entry.id());
-                // Change project
-                if let Some(project_id) = &time_entry.project_id {
-                    app.projects.selected = vec![project_id.clone()];
-                }
-                // Change tags
-                if let Some(tag_ids) = &time_entry.tag_ids {
-                    app.tags.selected = tag_ids.clone();
-                }
-                // Change description
-                app.description.text = time_entry.description.clone().ok_or(Error::MissingData)?;
+            if let Some(e) = current_time.end_time() {
+                Paragraph::new(format!("{}: {}", "End", app.formatter.date_time(e))).render(current_entry_chunks[5], buf); 
             }
         }
     }
+}
 
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]);
-    
-    // Display current time entry
-    let current_entry_chunks = Layout::default()
-        .constraints([
-            Constraint::Length(1), // Description
-            Constraint::Length(1), // Task
-            Constraint::Length(1), // Tag
-            Constraint::Length(1), // Project
-            Constraint::Length(1), // Start
-            Constraint::Length(1), // Stop
-        ].as_ref())
-        .split(chunks[1]);
-    // Description
-    f.render_widget(Paragraph::new(format!("{}: {}", "Description", app.description.text.clone())), current_entry_chunks[0]); 
-
-    // Task
-    let task: Option<&Task> = app.tasks.get_selected_item();
-    let task_text : String = match task {
-        Some(t) => t.name.clone(), 
-        None => String::from("")
-    };
-    f.render_widget(Paragraph::new(format!("{}: {}", "Task", task_text)), current_entry_chunks[1]);
-    // Tag
-    let tags: Vec<&Tag> = app.tags.get_selected_items();
-    let tag_string = tags.iter().map(|x| x.to_string() + ", ").collect::<String>();
-    let tag_string = tag_string.trim_end_matches(", ");
-    f.render_widget(Paragraph::new(format!("{}: {}", "Tag", tag_string)), current_entry_chunks[2]); 
-    // Project
-    let project: Option<&Project> = app.projects.get_selected_item();
-    let project_text : String = match project {
-        Some(project) => project.name.clone(), 
-        None => String::from("")
-    };
-    f.render_widget(Paragraph::new(format!("{}: {}", "Project", project_text)), current_entry_chunks[3]); 
-    //Time Entries
-    if let Some(time_entry_id) = app.current_entry_id.clone() {
-        let current_time = client.get(format!("{}/workspaces/{}/time-entries/{}", app.config.base_url, app.config.workspace_id.as_ref().ok_or(Error::MissingWorkspace)?.clone(), time_entry_id))
-            .header("X-API-KEY", app.config.api_key.as_ref().ok_or(Error::MissingApiKey)?.clone())
-            .send()?
-            .json::<TimeEntry>()?;
-        // Start
-        f.render_widget(Paragraph::new(format!("{}: {}", "Start: ", current_time.time_interval.clone().ok_or(Error::MissingData)?.start.ok_or(Error::MissingData)?)), current_entry_chunks[4]); 
-        // End
-        let end : Option<String> = current_time.time_interval.ok_or(Error::MissingData)?.end;
-        if let Some(e) = end {
-            f.render_widget(Paragraph::new(format!("{}: {}", "End: ", e)), current_entry_chunks[5]); 
+// Onboarding
+pub struct Onboarding;
+
+impl Screen for Onboarding {
+    fn title(&self) -> &'static str {
+        "Sign in"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        const KEYS: [(KeyCode, Action); 2] = [
+            (KeyCode::Char('i'), Action::SetMode(AppMode::Edit)), 
+            (KeyCode::Enter, Action::Confirm), 
+        ];
+        &KEYS
+    }
+
+    // Nothing else works without an API key
+    fn global_keys(&self) -> bool {
+        false
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Type(_) | Action::Backspace if app.current_mode == AppMode::Edit => edit_text(&mut app.api_key_input.text, action), 
+            Action::Confirm if !app.api_key_input.text.trim().is_empty() => {
+                Some(vec![Effect::ValidateApiKey(app.api_key_input.text.clone())])
+            }, 
+            _ => None, 
         }
     }
 
-   Ok(())
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        // Only the last characters of the key are shown
+        let mut input = app.api_key_input.clone();
+        let length = input.text.chars().count();
+        input.text = input.text
+            .chars()
+            .enumerate()
+            .map(|(i, c)| if i + 4 < length { '*' } else { c })
+            .collect();
+        input.render(area, buf);
+    }
 }
 
 // Workspace selection
-pub fn workspace_selection<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, key: Option<KeyEvent>) -> Result<(), Error> { 
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]); 
-    refresh_workspaces(client, app, false)?;
-    app.workspaces.render(f, chunks[1]);
-    
-    // Key Event
-    if let Some(event) = key {
-        app.workspaces.key_event(event, &app.current_mode);
-        match event.code {
-            KeyCode::Enter => {
-                app.config.workspace_id = app.workspaces.get_selected_item().ok_or(Error::MissingWorkspace)?.id.clone();
-            }, 
-            KeyCode::Char(c) => {
-                if c == 'r' {
-                        refresh_workspaces(client, app, true)?;
+pub struct WorkspaceSelection;
+
+impl Screen for WorkspaceSelection {
+    fn title(&self) -> &'static str {
+        "Workspaces"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &LIST_KEYS
+    }
+
+    fn list<'b>(&self, app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        Some(&mut app.workspaces)
+    }
+
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![Effect::LoadWorkspaces { force: false }]
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Confirm => {
+                if app.current_mode == AppMode::Navigation {
+                    app.workspaces.toggle();
+                }
+                if let Some(workspace) = app.workspaces.get_selected_item() {
+                    app.config.workspace_id = workspace.id.clone();
+                    // End of the first run: save the key and workspace, then load the rest
+                    if app.onboarding {
+                        return Some(vec![Effect::FinishOnboarding]);
+                    }
                 }
+                Some(vec![])
             }, 
-           _ => {}
+            Action::Refresh => Some(vec![Effect::LoadWorkspaces { force: true }]), 
+            _ => None, 
         }
     }
-    Ok(())
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        app.workspaces.render(area, buf);
+    }
 }
 
 // Time Entry Selection
-pub fn time_entry_selection<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, key: Option<KeyEvent>) -> Result<(), Error> {
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]); 
-    refresh_time_entries(client, app, false)?;
-
-    // Refresh data feeds
-    refresh_workspaces(client, app, false)?;
-    refresh_projects(client, app, false)?;
-    refresh_tags(client, app, false)?;
-
-    // Time Entry table
-    let mut title = app.time_entries.title.clone();
-    if !app.time_entries.search_text.is_empty() {
-        title = format!("{}{}", title, app.time_entries.search_text);
-    }
-    let items : Vec<TimeEntry> = if app.time_entries.search_text.is_empty() {
-        app.time_entries.items.to_vec()
-    } else {
-        app.time_entries.search(&app.time_entries.search_text)
-    }; 
-    let table = Table::new(
-        items
-        .iter()
-        .map(|entry| {
-            // Project name
-            let mut project = String::new();
-            if let Some(project_id) = &entry.project_id {
-                if let Some(p) = app.projects.get_by_id(project_id.to_string()) {
-                    project = p.to_string();
-                }
-            }
-            // Task name
-            let mut task = String::new();
-            if let Some(task_id) = &entry.task_id {
-                if let Some(t) = app.tasks.get_by_id(task_id.to_string()) {
-                    task = t.to_string();
-                }
-            }
-            // Tag names
-            let mut tags = vec![];
-            if let Some(tag_ids) = &entry.tag_ids {
-                tags = tag_ids
-                    .iter()
-                    .map(|tag_id| {
-                        if let Some(t) = app.tags.get_by_id(tag_id.clone()) {
-                            t.to_string()
-                        } else {
-                            String::from("Unknown")
-                        }
-                    }).collect::<Vec<String>>();
-            }
-            let tag_string = tags.iter().map(|x| x.to_string() + ",").collect::<String>();
-            let tag_string = tag_string.trim_end_matches(',');
-            // Start, end, duration
-            let mut start = String::new();
-            let mut end = String::new();
-            let mut duration = String::new(); 
-            if let Some(time_interval) = &entry.time_interval {
-                // Start
-                if let Some(s) = &time_interval.start {
-                    start = s.clone();
+pub struct TimeEntrySelection;
+
+// Rows of the time entry table, below its title and header
+fn time_entry_rows_area(area: Rect) -> Rect {
+    let rows_area = Block::default().title("").inner(area);
+    Rect { y: rows_area.y + 1, height: rows_area.height.saturating_sub(1), ..rows_area }
+}
+
+impl Screen for TimeEntrySelection {
+    fn title(&self) -> &'static str {
+        "Time entries"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        const KEYS: [(KeyCode, Action); 10] = keys(LIST_KEYS, [
+            (KeyCode::Char('a'), Action::ContinueEntry), 
+            (KeyCode::Char('v'), Action::ToggleTimesheet), 
+            (KeyCode::Char('f'), Action::EditFilter), 
+        ]);
+        &KEYS
+    }
+
+    fn list<'b>(&self, app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        Some(&mut app.time_entries)
+    }
+
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![
+            Effect::LoadTimeEntries { force: false },
+            Effect::LoadWorkspaces { force: false },
+            Effect::LoadProjects { force: false },
+            Effect::LoadTags { force: false },
+        ]
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Confirm => {
+                if let Some(time_entry) = app.time_entries.get_highlighted_item().cloned() {
+                    app.select_entry(&time_entry);
+                    app.current_entry_id = time_entry.id.clone();
+                    app.time_entries.search_text = String::new();
+                    return Some(show(app, ScreenId::Home));
                 }
+                Some(vec![])
+            }, 
+            Action::Refresh => Some(vec![
+                Effect::LoadTimeEntries { force: true },
+                Effect::LoadWorkspaces { force: true },
+                Effect::LoadProjects { force: true },
+                Effect::LoadTags { force: true },
+            ]), 
+            // Continue: start the highlighted entry again
+            Action::ContinueEntry => Some(match app.time_entries.get_highlighted_item() {
+                Some(time_entry) => vec![Effect::ContinueEntry(time_entry.id())], 
+                None => vec![], 
+            }), 
+            Action::ToggleTimesheet => {
+                app.timesheet_view = !app.timesheet_view;
+                Some(vec![])
+            }, 
+            Action::EditFilter => {
+                app.filter_form = FilterForm::from(&app.time_entry_filter);
+                app.navigate(ScreenId::TimeEntryFilter);
+                Some(vec![])
+            }, 
+            _ => None, 
+        }
+    }
+
+    fn list_area(&self, area: Rect) -> Rect {
+        time_entry_rows_area(area)
+    }
+
+    fn refresh_rows(&self, app: &mut App) {
+        app.time_entries.rows = if app.timesheet_view {
+            Some(timesheet_rows(app, &app.time_entries.visible_items()).1)
+        } else {
+            None
+        };
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        // Time Entry table
+        let mut title = app.time_entries.title.clone();
+        if !app.time_entry_filter.is_empty() {
+            title = format!("{}[{}] ", title, filter_description(app));
+        }
+        if !app.time_entries.search_text.is_empty() {
+            title = format!("{}{}", title, app.time_entries.search_text);
+        }
+        let block = Block::default().title(title);
+        let items : Vec<TimeEntry> = app.time_entries.visible_items();
+        // The rows may have changed since the last action
+        let mut selected_row = app.time_entries.state.selected();
+        let (rows, widths, header) = if app.timesheet_view {
+            let (rows, shown) = timesheet_rows(app, &items);
+            selected_row = selected_row.and_then(|selected| shown.iter().position(|i| *i == Some(selected)));
+            (
+                rows, 
+                // Notes fit "overlap 10:30"
+                vec![Constraint::Percentage(18), Constraint::Percentage(12), Constraint::Percentage(12), Constraint::Percentage(10), Constraint::Percentage(7), Constraint::Percentage(7), Constraint::Percentage(8), Constraint::Length(13)], 
+                vec!["Description", "Project", "Task", "Tag(s)", "Start", "End", "Duration", "Notes"], 
+            )
+        } else {
+            let rows = items
+                .iter()
+                .map(|entry| {
+                    let mut cells = entry_cells(app, entry);
+                    // Start, end, duration
+                    let start = entry.start_time().map(|s| app.formatter.date_time(s)).unwrap_or_default();
+                    let end = match entry.end_time() {
+                        Some(e) => app.formatter.date_time(e), 
+                        None if entry.is_running() => String::from("running"), 
+                        None => String::new(), 
+                    };
+                    let duration = entry.duration().map(format_duration).unwrap_or_default();
+                    cells.extend([start, end, duration]);
+                    Row::new(cells)
+                })
+                .collect::<Vec<Row>>();
+            (
+                rows, 
+                // Start and end fit "2024-03-12 14:00"
+                vec![Constraint::Percentage(20), Constraint::Percentage(12), Constraint::Percentage(12), Constraint::Percentage(10), Constraint::Length(16), Constraint::Length(16), Constraint::Length(8)], 
+                vec!["Description", "Project", "Task", "Tag(s)", "Start", "End", "Duration"], 
+            )
+        };
+        let offset = scroll_offset(selected_row, time_entry_rows_area(area).height);
+        let table = Table::new(rows.into_iter().skip(offset))
+            .block(block)
+            .header(Row::new(header))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).add_modifier(Modifier::ITALIC).add_modifier(Modifier::UNDERLINED))
+            .column_spacing(2);
+        // Table State
+        let mut state = TableState::default();
+        if let Some(i) = selected_row {
+            state.select(Some(i - offset));
+        }
+        StatefulWidget::render(table, area, buf, &mut state);
+    }
+}
 
-                // End
-                if let Some(e) = &time_interval.end {
-                    end = e.clone();
+// Description, project, task and tag names of an entry
+fn entry_cells(app: &App, entry: &TimeEntry) -> Vec<String> {
+    // Project name
+    let mut project = String::new();
+    if let Some(project_id) = &entry.project_id {
+        if let Some(p) = app.projects.get_by_id(project_id.to_string()) {
+            project = p.to_string();
+        }
+    }
+    // Task name, app.tasks only holds the tasks of the selected project
+    let mut task = String::new();
+    if let Some(task_id) = &entry.task_id {
+        if let Some(t) = app.tasks.get_by_id(task_id.to_string()) {
+            task = t.to_string();
+        } else if let Some(t) = &entry.task {
+            task = t.to_string();
+        }
+    }
+    // Tag names
+    let mut tags = vec![];
+    if let Some(tag_ids) = &entry.tag_ids {
+        tags = tag_ids
+            .iter()
+            .map(|tag_id| {
+                if let Some(t) = app.tags.get_by_id(tag_id.clone()) {
+                    t.to_string()
+                } else {
+                    String::from("Unknown")
                 }
+            }).collect::<Vec<String>>();
+    }
+    vec![
+        entry.description.clone().unwrap_or_default(), 
+        project, 
+        task, 
+        tags.join(","), 
+    ]
+}
+
+// Flag time between two consecutive entries of a day
+fn gap_note(earlier: &TimeEntry, later: &TimeEntry) -> Option<String> {
+    let gap = later.start_time()? - earlier.end_time()?;
+    if gap >= Duration::minutes(1) {
+        Some(format!("gap {}", format_duration(gap)))
+    } else if gap <= Duration::minutes(-1) {
+        Some(format!("overlap {}", format_duration(-gap)))
+    } else {
+        None
+    }
+}
+
+fn total_duration(entries: &[&TimeEntry]) -> Duration {
+    entries
+        .iter()
+        .filter_map(|entry| entry.duration())
+        .fold(Duration::zero(), |total, d| total + d)
+}
+
+// Entries grouped under week and day headers, newest first, in the user's zone.
+// Returns the rows along with the item index shown on each row.
+fn timesheet_rows(app: &App, items: &[TimeEntry]) -> (Vec<Row<'static>>, Vec<Option<usize>>) {
+    let mut order : Vec<(usize, DateTime<Utc>)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| entry.start_time().map(|s| (i, s)))
+        .collect();
+    order.sort_by_key(|(_, start)| std::cmp::Reverse(*start));
+    let mut days : Vec<(NaiveDate, Vec<usize>)> = vec![];
+    for (i, start) in order {
+        let day = app.formatter.local_date(start);
+        match days.last_mut() {
+            Some((date, indices)) if *date == day => indices.push(i), 
+            _ => days.push((day, vec![i])), 
+        }
+    }
+    let week_of = |date: &NaiveDate| week_containing(*date, app.formatter.week_start);
+    let header = |label: String, total: Duration| {
+        Row::new(vec![label, String::new(), String::new(), String::new(), String::new(), String::new(), format_duration(total), String::new()])
+            .style(Style::default().add_modifier(Modifier::BOLD))
+    };
+
+    let mut rows = vec![];
+    let mut row_map = vec![];
+    let mut current_week = None;
+    for (date, indices) in &days {
+        // Week header
+        let week = week_of(date);
+        if current_week != Some(week) {
+            let week_entries = days
+                .iter()
+                .filter(|(d, _)| week_of(d) == week)
+                .flat_map(|(_, indices)| indices.iter().map(|i| &items[*i]))
+                .collect::<Vec<&TimeEntry>>();
+            rows.push(header(format!("Week of {}", app.formatter.date(week)), total_duration(&week_entries)));
+            row_map.push(None);
+            current_week = Some(week);
+        }
+        // Day header
+        let day_entries = indices.iter().map(|i| &items[*i]).collect::<Vec<&TimeEntry>>();
+        rows.push(header(format!("  {}", app.formatter.day(*date)), total_duration(&day_entries)));
+        row_map.push(None);
+        // Entries, the one before each entry in time is the next in the list
+        for (n, i) in indices.iter().enumerate() {
+            let entry = &items[*i];
+            let mut cells = entry_cells(app, entry);
+            let start = entry.start_time().map(|s| app.formatter.time(s)).unwrap_or_default();
+            let end = match entry.end_time() {
+                Some(e) => app.formatter.time(e), 
+                None => String::from("running"), 
+            };
+            let duration = entry.duration().map(format_duration).unwrap_or_default();
+            let note = indices.get(n + 1).and_then(|earlier| gap_note(&items[*earlier], entry)).unwrap_or_default();
+            cells.extend([start, end, duration, note]);
+            rows.push(Row::new(cells));
+            row_map.push(Some(*i));
+        }
+    }
+    (rows, row_map)
+}
 
-                // Duration
-                if let Some(d) = &time_interval.duration {
-                    duration = d.clone();
+// Active time entry filters as a short description
+fn filter_description(app: &App) -> String {
+    let filter = &app.time_entry_filter;
+    let mut parts : Vec<String> = vec![];
+    match (filter.start, filter.end) {
+        (Some(s), Some(e)) => parts.push(format!("{} to {}", s, e)), 
+        (Some(s), None) => parts.push(format!("from {}", s)), 
+        (None, Some(e)) => parts.push(format!("until {}", e)), 
+        (None, None) => {}
+    }
+    if let Some(project_id) = &filter.project_id {
+        let name = app.projects.get_by_id(project_id.clone()).map(|p| p.to_string()).unwrap_or_else(|| project_id.clone());
+        parts.push(format!("project: {}", name));
+    }
+    if let Some(task_id) = &filter.task_id {
+        let name = app.tasks.get_by_id(task_id.clone()).map(|t| t.to_string()).unwrap_or_else(|| task_id.clone());
+        parts.push(format!("task: {}", name));
+    }
+    if let Some(tag_id) = &filter.tag_id {
+        let name = app.tags.get_by_id(tag_id.clone()).map(|t| t.to_string()).unwrap_or_else(|| tag_id.clone());
+        parts.push(format!("tag: {}", name));
+    }
+    match filter.billable {
+        Some(true) => parts.push(String::from("billable")), 
+        Some(false) => parts.push(String::from("non-billable")), 
+        None => {}
+    }
+    if filter.running_only {
+        parts.push(String::from("running"));
+    }
+    parts.join(", ")
+}
+
+
+// Time Entry Filter
+pub struct TimeEntryFilter;
+
+impl Screen for TimeEntryFilter {
+    fn title(&self) -> &'static str {
+        "Filter"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        const KEYS: [(KeyCode, Action); 6] = keys(MOVE_KEYS, INPUT_KEYS);
+        &KEYS
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Next => app.filter_form.next(), 
+            Action::Previous => app.filter_form.previous(), 
+            Action::Clear => app.filter_form.clear(), 
+            Action::Type(_) | Action::Backspace if app.current_mode == AppMode::Edit => {
+                return app.filter_form.text_mut().and_then(|text| edit_text(text, action));
+            }, 
+            Action::Confirm if app.current_mode == AppMode::Navigation => {
+                let form = &mut app.filter_form;
+                match form.highlighted() {
+                    FilterField::Project => {
+                        form.filter.project_id = match form.filter.project_id {
+                            Some(_) => None, 
+                            None => app.projects.get_selected_item().map(|p| p.id()), 
+                        };
+                    }, 
+                    FilterField::Task => {
+                        form.filter.task_id = match form.filter.task_id {
+                            Some(_) => None, 
+                            None => app.tasks.get_selected_item().map(|t| t.id()), 
+                        };
+                    }, 
+                    FilterField::Tag => {
+                        form.filter.tag_id = match form.filter.tag_id {
+                            Some(_) => None, 
+                            None => app.tags.get_selected_item().map(|t| t.id()), 
+                        };
+                    }, 
+                    FilterField::Apply => match form.to_filter() {
+                        Ok(filter) => {
+                            app.time_entry_filter = filter;
+                            // Back to the list the form was opened from
+                            if app.history.last() == Some(&ScreenId::TimeEntrySelection) {
+                                app.back();
+                            } else {
+                                app.navigate(ScreenId::TimeEntrySelection);
+                            }
+                            return Some(vec![Effect::LoadTimeEntries { force: true }]);
+                        }, 
+                        Err(e) => form.error = Some(e), 
+                    }, 
+                    _ => form.toggle(), 
                 }
-            }
-            return Row::new(vec![
-                entry.description.as_ref().unwrap().clone(), 
-                project,
-                task, 
-                tag_string.to_owned(), 
-                start, 
-                end,
-                duration
-            ]);
-        })
-    )
-        .block(Block::default().title(title))
-        .header(Row::new(vec!["Description", "Project", "Task", "Tag(s)", "Start", "End", "Duration"]))
-        .widths(&[Constraint::Percentage(20), Constraint::Percentage(16), Constraint::Percentage(16), Constraint::Percentage(16), Constraint::Percentage(16), Constraint::Percentage(16), Constraint::Percentage(16)])
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD).add_modifier(Modifier::ITALIC).add_modifier(Modifier::UNDERLINED))
-        .column_spacing(2);
-    // Table State
-    let mut state = TableState::default();
-    if let Some(i) = app.time_entries.state.selected() {
-        state.select(Some(i));
-    }
-    f.render_stateful_widget(table, chunks[1], &mut state);
-
-    // Key Event
-    if let Some(event) = key {
-        match event.code {
-            KeyCode::Enter => {
-                let time_entry : &TimeEntry = app.time_entries.get_highlighted_item().ok_or(Error::MissingTimeEntry)?;
-                // Change project
-                if let Some(project_id) = &time_entry.project_id {
-                    app.projects.selected = vec![project_id.clone()];
+            }, 
+            _ => return None, 
+        }
+        Some(vec![])
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        let area = Layout::default()
+            .margin(5)
+            .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+            .split(area);
+        let help = match &app.filter_form.error {
+            Some(e) => e.clone(), 
+            None => String::from("Enter: use the current selection or toggle, i: edit dates (YYYY-MM-DD), c: clear"), 
+        };
+        Paragraph::new(help).render(area[0], buf);
+        let form = &app.filter_form;
+        let name_or_any = |name: Option<String>| name.unwrap_or_else(|| String::from("Any"));
+        let rows : Vec<ListItem> = FilterField::ALL
+            .iter()
+            .map(|field| {
+                let value = match field {
+                    FilterField::Start => form.start.clone(), 
+                    FilterField::End => form.end.clone(), 
+                    FilterField::Project => name_or_any(form.filter.project_id.as_ref().map(|id| app.projects.get_by_id(id.clone()).map(|p| p.to_string()).unwrap_or_else(|| id.clone()))), 
+                    FilterField::Task => name_or_any(form.filter.task_id.as_ref().map(|id| app.tasks.get_by_id(id.clone()).map(|t| t.to_string()).unwrap_or_else(|| id.clone()))), 
+                    FilterField::Tag => name_or_any(form.filter.tag_id.as_ref().map(|id| app.tags.get_by_id(id.clone()).map(|t| t.to_string()).unwrap_or_else(|| id.clone()))), 
+                    FilterField::Billable => match form.filter.billable {
+                        Some(true) => String::from("Billable"), 
+                        Some(false) => String::from("Non-billable"), 
+                        None => String::from("Any"), 
+                    }, 
+                    FilterField::RunningOnly => String::from(if form.filter.running_only { "Yes" } else { "No" }), 
+                    FilterField::Apply => return ListItem::new(field.to_string()), 
+                };
+                ListItem::new(format!("{}: {}", field, value))
+            })
+            .collect();
+        let list = List::new(rows)
+            .block(Block::default().borders(Borders::ALL).title("Filter time entries"))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).add_modifier(Modifier::UNDERLINED).add_modifier(Modifier::ITALIC));
+        StatefulWidget::render(list, area[1], buf, &mut app.filter_form.state.clone());
+    }
+}
+
+// Weekly Grid
+pub struct WeeklyGrid;
+
+// Project and task names of a grid row
+fn grid_row_label(app: &App, row: &GridRow) -> String {
+    // Names come from hydrated entries, falling back to the loaded lists
+    let entry = app.grid.entries.iter().flatten().find(|entry| row.contains(entry));
+    let project = row.project_id.as_ref().map(|id| {
+        entry.and_then(|e| e.project.as_ref()).map(|p| p.name.clone())
+            .or_else(|| app.projects.get_by_id(id.clone()).map(|p| p.name.clone()))
+            .unwrap_or_else(|| id.clone())
+    }).unwrap_or_else(|| String::from("No project"));
+    match &row.task_id {
+        Some(id) => {
+            let task = entry.and_then(|e| e.task.as_ref()).map(|t| t.name.clone())
+                .or_else(|| app.tasks.get_by_id(id.clone()).map(|t| t.name.clone()))
+                .unwrap_or_else(|| id.clone());
+            format!("{} / {}", project, task)
+        }, 
+        None => project, 
+    }
+}
+
+
+impl Screen for WeeklyGrid {
+    fn title(&self) -> &'static str {
+        "Weekly grid"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        const KEYS: [(KeyCode, Action); 9] = keys(MOVE_KEYS, [
+            (KeyCode::Left, Action::Left), 
+            (KeyCode::Right, Action::Right), 
+            (KeyCode::Char('['), Action::PreviousWeek), 
+            (KeyCode::Char(']'), Action::NextWeek), 
+            (KeyCode::Char('r'), Action::Refresh), 
+        ]);
+        &KEYS
+    }
+
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![Effect::LoadProjects { force: false }, Effect::LoadGrid { force: false }]
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        let grid = &mut app.grid;
+        match action {
+            Action::Next => if grid.row + 1 < grid.rows.len() { grid.row += 1 }, 
+            Action::Previous => grid.row = grid.row.saturating_sub(1), 
+            Action::Left => grid.column = grid.column.saturating_sub(1), 
+            Action::Right => grid.column = (grid.column + 1).min(6), 
+            Action::PreviousWeek | Action::NextWeek => {
+                if let Some(week_start) = grid.week_start {
+                    let weeks = if *action == Action::PreviousWeek { -7 } else { 7 };
+                    grid.set_week(week_start + Duration::days(weeks));
+                    return Some(vec![Effect::LoadGrid { force: true }]);
                 }
-                // Change tags
-                if let Some(tag_ids) = &time_entry.tag_ids {
-                    app.tags.selected = tag_ids.clone();
+            }, 
+            Action::Refresh => return Some(vec![Effect::LoadGrid { force: true }]), 
+            Action::SetMode(AppMode::Edit) => {
+                grid.input = String::new();
+                app.current_mode = AppMode::Edit;
+            }, 
+            Action::Type(_) | Action::Backspace if app.current_mode == AppMode::Edit => return edit_text(&mut grid.input, action), 
+            Action::Confirm if app.current_mode == AppMode::Edit => {
+                app.current_mode = AppMode::Navigation;
+                let row = grid.selected_row().cloned();
+                let day = grid.selected_day();
+                match (row, day, parse_duration(&grid.input)) {
+                    (Some(row), Some(day), Some(total)) => return Some(vec![Effect::SetDuration(row, day, total)]), 
+                    (None, _, _) => grid.message = Some(String::from("Select a project (p) to add a row")), 
+                    _ => grid.message = Some(format!("Invalid duration: {}", grid.input)), 
                 }
-                // Change description
-                app.description.text = time_entry.description.clone().ok_or(Error::MissingData)?;
-                
-                // Change current_entry_id
-                app.current_entry_id = time_entry.id.clone();
-
-                // Change to home screen
-                app.current_screen = Screen::Home;
             }, 
-            KeyCode::Char(c) => {
-                if c == 'r' {
-                    refresh_time_entries(client, app, true)?;
-                    refresh_workspaces(client, app, true)?;
-                    refresh_projects(client, app, true)?;
-                    refresh_tags(client, app, true)?;
+            _ => return None, 
+        }
+        Some(vec![])
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        let area = Layout::default()
+            .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+            .split(area);
+        let help = match &app.grid.message {
+            Some(m) => m.clone(), 
+            None => String::from("Arrows/j/k: move, [ ]: previous/next week, i: type a duration (1:30, 1.5, 90m) and Enter to save"), 
+        };
+        Paragraph::new(help).render(area[0], buf);
+
+        let days = app.grid.days();
+        let editing = matches!(app.current_mode, AppMode::Edit);
+        let selected = Style::default().add_modifier(Modifier::REVERSED);
+        let mut rows : Vec<Row> = app.grid.rows
+            .iter()
+            .enumerate()
+            .map(|(r, row)| {
+                let mut cells = vec![Cell::from(grid_row_label(app, row))];
+                let mut row_total = Duration::zero();
+                for (c, day) in days.iter().enumerate() {
+                    let total = app.grid.cell_total(row, *day, &app.formatter);
+                    row_total += total;
+                    let is_selected = r == app.grid.row && c == app.grid.column;
+                    let text = if is_selected && editing {
+                        format!("{}_", app.grid.input)
+                    } else if total > Duration::zero() {
+                        format_duration(total)
+                    } else {
+                        String::new()
+                    };
+                    let cell = Cell::from(text);
+                    cells.push(if is_selected { cell.style(selected) } else { cell });
                 }
-            },
-            _ => {}
+                cells.push(Cell::from(format_duration(row_total)));
+                Row::new(cells)
+            })
+            .collect();
+        // Daily totals
+        let mut totals = vec![String::from("Total")];
+        let mut week_total = Duration::zero();
+        for day in &days {
+            let total = app.grid.day_total(*day, &app.formatter);
+            week_total += total;
+            totals.push(format_duration(total));
+        }
+        totals.push(format_duration(week_total));
+        rows.push(Row::new(totals).style(Style::default().add_modifier(Modifier::BOLD)));
+
+        let mut header = vec![String::from("Project / Task")];
+        header.extend(days.iter().map(|d| d.format("%a %d").to_string()));
+        header.push(String::from("Total"));
+        // Days and the total fit "12:30_" while typing, the rows take the rest
+        let mut widths = vec![Constraint::Min(20)];
+        widths.extend([Constraint::Length(8); 8]);
+        let title = match days.first() {
+            Some(d) => format!("Week of {}", app.formatter.date(*d)), 
+            None => String::new(), 
+        };
+        let table = Table::new(rows)
+            .block(Block::default().title(title))
+            .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+            .widths(&widths)
+            .column_spacing(1);
+        Widget::render(table, area[1], buf);
+    }
+}
+
+// Profile Selection
+pub struct ProfileSelection;
+
+impl Screen for ProfileSelection {
+    fn title(&self) -> &'static str {
+        "Profiles"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &LIST_KEYS
+    }
+
+    fn list<'b>(&self, app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        Some(&mut app.profiles)
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Confirm => Some(match app.profiles.get_highlighted_item().map(|profile| profile.name.clone()) {
+                Some(name) => {
+                    // Keep the active profile marked if the switch is refused
+                    app.profiles.selected = vec![app.profile.clone()];
+                    vec![Effect::SwitchProfile(name)]
+                }, 
+                None => vec![], 
+            }), 
+            _ => None, 
         }
-        app.time_entries.key_event(event, &app.current_mode);
     }
-    Ok(())
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        app.profiles.render(area, buf);
+    }
 }
 
 // Project Selection
-pub fn project_selection<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, key: Option<KeyEvent>) -> Result<(), Error> {
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]);
-    refresh_projects(client, app, false)?;
-
-    app.projects.render(f, chunks[1]);
-
-    // Key Event
-    if let Some(event) = key {
-        app.projects.key_event(event, &app.current_mode);
-        if let KeyCode::Char(c) = event.code {
-            if c == 'r' {
-                refresh_projects(client, app, true)?;
-            }
-       }
-   }
-    Ok(())
+pub struct ProjectSelection;
+
+impl Screen for ProjectSelection {
+    fn title(&self) -> &'static str {
+        "Projects"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &LIST_KEYS
+    }
+
+    fn list<'b>(&self, app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        Some(&mut app.projects)
+    }
+
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![Effect::LoadProjects { force: false }]
+    }
+
+    fn handle_action(&self, _app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Refresh => Some(vec![Effect::LoadProjects { force: true }]), 
+            _ => None, 
+        }
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        app.projects.render(area, buf);
+    }
 }
 
 // Task Selection
-pub fn task_selection<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, key: Option<KeyEvent>) -> Result<(), Error> {
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]);
-    // Ensure that a project is set in the config
-    refresh_projects(client, app, false)?;
-    if let Some(project_id) = app.projects.get_selected_item() {
-        app.config.project_id = Some(project_id.clone().id());
-    } else {
-        app.config.project_id = Some(app.projects.items.get(0).ok_or(Error::MissingData)?.clone().id());
+pub struct TaskSelection;
+
+impl Screen for TaskSelection {
+    fn title(&self) -> &'static str {
+        "Tasks"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &LIST_KEYS
     }
-    refresh_tasks(client, app, false)?;
-    app.tasks.render(f, chunks[1]);
 
-    // Key Event
-    if let Some(event) = key {
-        app.tasks.key_event(event, &app.current_mode);
-        if let KeyCode::Char(c) = event.code {
-            if c == 'r' {
-                refresh_tasks(client, app, true)?;
+    fn list<'b>(&self, app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        Some(&mut app.tasks)
+    }
+
+    fn on_show(&self, app: &mut App) -> Vec<Effect> {
+        // If selected project has changed, clear tasks
+        if let (Some(config_project_id), Some(selected_project)) = (&app.config.project_id, app.projects.get_selected_item()) {
+            if *config_project_id != selected_project.id() {
+                app.tasks.items = vec![];
             }
-       }
+        }
+        vec![Effect::LoadTasks { force: false }]
+    }
+
+    fn handle_action(&self, _app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Refresh => Some(vec![Effect::LoadTasks { force: true }]), 
+            _ => None, 
+        }
     }
-    Ok(())
-}
 
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        app.tasks.render(area, buf);
+    }
+}
 
 // Tag Selection
-pub fn tag_selection<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, key: Option<KeyEvent>) -> Result<(), Error> {
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]);
-    refresh_tags(client, app, false)?;
-    app.tags.render(f, chunks[1]);
-
-    // Key Event
-    if let Some(event) = key {
-        app.tags.key_event(event, &app.current_mode);
-        if let KeyCode::Char(c) = event.code {
-            if c == 'r' {
-                refresh_tags(client, app, true)?;
-            }
+pub struct TagSelection;
+
+impl Screen for TagSelection {
+    fn title(&self) -> &'static str {
+        "Tags"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &LIST_KEYS
+    }
+
+    fn list<'b>(&self, app: &'b mut App) -> Option<&'b mut dyn ListControl> {
+        Some(&mut app.tags)
+    }
+
+    fn on_show(&self, _app: &mut App) -> Vec<Effect> {
+        vec![Effect::LoadTags { force: false }]
+    }
+
+    fn handle_action(&self, _app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Refresh => Some(vec![Effect::LoadTags { force: true }]), 
+            _ => None, 
         }
-   }
-    Ok(())
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        app.tags.render(area, buf);
+    }
 }
 
 // Description Input
-pub fn description_input<B: Backend>(f: &mut Frame<B>, client: &Client, app: &mut App, key: Option<KeyEvent>) -> Result<(), Error> {
-    // App Title
-    let chunks = template_screen(f, client, app);
-    f.render_widget(Paragraph::new(app.to_string()), chunks[0]);
+pub struct DescriptionEdit;
+
+impl Screen for DescriptionEdit {
+    fn title(&self) -> &'static str {
+        "Description"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &INPUT_KEYS
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Clear => {
+                app.description.text = String::new();
+                Some(vec![])
+            }, 
+            Action::Type(_) | Action::Backspace if app.current_mode == AppMode::Edit => edit_text(&mut app.description.text, action), 
+            Action::Confirm => Some(show(app, ScreenId::Home)), 
+            _ => None, 
+        }
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        app.description.render(area, buf);
+    }
+}
 
-    // Description
-    app.description.render(f, chunks[1]);
+// Time Input
+pub struct TimeInput;
+
+impl Screen for TimeInput {
+    fn title(&self) -> &'static str {
+        "Time input"
+    }
+
+    fn keymap(&self) -> &'static [(KeyCode, Action)] {
+        &INPUT_KEYS
+    }
+
+    fn on_show(&self, app: &mut App) -> Vec<Effect> {
+        app.current_mode = AppMode::Edit;
+        vec![]
+    }
+
+    fn handle_action(&self, app: &mut App, action: &Action) -> Option<Vec<Effect>> {
+        match action {
+            Action::Clear => app.time_input.text = String::new(), 
+            Action::Type(_) | Action::Backspace if app.current_mode == AppMode::Edit => return edit_text(&mut app.time_input.text, action), 
+            Action::Confirm => match app.parse_time(&app.time_input.text) {
+                Ok(time) => return Some(vec![Effect::StartAt(time)]), 
+                Err(Error::InvalidTime(reason)) => app.message = Some(format!("Not understood ({})", reason)), 
+                Err(e) => app.message = Some(e.to_string()), 
+            }, 
+            _ => return None, 
+        }
+        Some(vec![])
+    }
+
+    fn render(&self, app: &App, area: Rect, buf: &mut Buffer) {
+        let area = Layout::default()
+            .constraints([Constraint::Min(0), Constraint::Length(6)].as_ref())
+            .split(area);
+        app.time_input.render(area[0], buf);
+
+        // Preview of the parsed expression
+        let input = &app.time_input.text;
+        let preview = if input.trim().is_empty() {
+            String::new()
+        } else {
+            match app.parse_time(input) {
+                Ok(time) => describe_time(&app.formatter, &time), 
+                Err(Error::InvalidTime(reason)) => format!("Not understood ({})", reason), 
+                Err(e) => e.to_string(), 
+            }
+        };
+        Paragraph::new(format!("{}\n\nEnter starts the timer, or adds the entry for a range.", preview)).render(area[1], buf);
+    }
+}
 
-    // Key Event
-    if let Some(event) = key {
-        app.description.key_event(event, &app.current_mode); 
-        if event.code == KeyCode::Enter { app.current_screen = Screen::Home }
+pub fn describe_time(formatter: &Formatter, time: &TimeExpr) -> String {
+    let format = |t: DateTime<Utc>| format!("{} {}", formatter.day(formatter.local_date(t)), formatter.time(t));
+    match time {
+        TimeExpr::At(start) => format!("Start at {}", format(*start)), 
+        TimeExpr::Range(start, end) => format!(
+            "From {} to {} ({})", 
+            format(*start), 
+            formatter.time(*end), 
+            format_duration(*end - *start)
+        ), 
     }
-    Ok(())
-    
 }
@@ -0,0 +1,300 @@
+use crate::{
+    clockify::{App, AppMode},
+    ui::{
+        action::Action,
+        effect::Effect,
+        screen,
+        ScreenId,
+    },
+};
+
+// Apply an action to the state. Nothing is requested from Clockify here,
+// the loads and changes to make are returned in order for perform().
+pub fn update(app: &mut App, action: Action) -> Vec<Effect> {
+    if !matches!(action, Action::Tick | Action::Mouse(..)) {
+        app.grid.message = None;
+        app.filter_form.error = None;
+    }
+    // The current screen handles its own actions first
+    let screen = app.current_screen.screen();
+    screen.refresh_rows(app);
+    if let Some(effects) = screen.handle_action(app, &action) {
+        return effects;
+    }
+    let navigation = app.current_mode == AppMode::Navigation;
+    let search = app.current_mode == AppMode::Search;
+    match action {
+        Action::Quit => app.should_quit = true,
+        Action::Show(screen) => return show(app, screen),
+        Action::Back => if app.back() { return app.current_screen.screen().on_show(app) },
+        Action::Forward => if app.forward() { return app.current_screen.screen().on_show(app) },
+        Action::SetMode(mode) => app.current_mode = mode,
+        Action::StartTimer => return vec![Effect::StartTimer],
+        Action::StopTimer => return vec![Effect::StopTimer],
+        Action::UpdateEntry => return vec![Effect::UpdateEntry],
+        Action::Mouse(mouse, area) => {
+            // Clicks and scrolling act on the list of the current screen
+            let area = screen::list_area(app, area);
+            if let Some(action) = screen.list(app).and_then(|list| list.mouse_event(mouse, area)) {
+                return update(app, action);
+            }
+        },
+        Action::Tick => return vec![Effect::Poll],
+        // The rest moves the list of the screen, if it has one
+        _ => if let Some(list) = screen.list(app) {
+            match action {
+                Action::Next => list.next(),
+                Action::Previous => list.previous(),
+                Action::Clear => list.clear_selected(),
+                Action::Confirm if navigation => list.toggle(),
+                Action::Type(c) if search => list.search_push(c),
+                Action::Backspace if search => list.search_pop(),
+                _ => {},
+            }
+        },
+    }
+    vec![]
+}
+
+// Switch screens, loading what the new one shows
+pub fn show(app: &mut App, screen: ScreenId) -> Vec<Effect> {
+    app.navigate(screen);
+    screen.screen().on_show(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crate::{
+        api::project::Project,
+        clockify::ConfigFile,
+        ui::{action, components::{FilterField, Id}, grid::GridRow},
+    };
+
+    fn app() -> App<'static> {
+        let mut app = App::new("Clockify", ConfigFile::default(), String::from("default"));
+        app.config.workspace_id = Some(String::from("ws1"));
+        app.config.user_id = Some(String::from("user1"));
+        app.current_screen = ScreenId::Home;
+        app
+    }
+
+    fn key(app: &App, c: char) -> Option<Action> {
+        action::from_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    fn project(id: &str, name: &str) -> Project {
+        Project { id: Some(id.to_owned()), ..Project::from(name) }
+    }
+
+    #[test]
+    fn keys() {
+        let mut app = app();
+        assert_eq!(key(&app, 'p'), Some(Action::Show(ScreenId::ProjectSelection)));
+        assert_eq!(key(&app, 'e'), Some(Action::StopTimer));
+        assert_eq!(key(&app, 'x'), None);
+        let quit = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(action::from_key(&app, quit), Some(Action::Quit));
+
+        // Screen keys come first
+        app.current_screen = ScreenId::TimeEntrySelection;
+        assert_eq!(key(&app, 'f'), Some(Action::EditFilter));
+        assert_eq!(key(&app, 'j'), Some(Action::Next));
+        assert_eq!(key(&app, 'r'), Some(Action::Refresh));
+        app.current_screen = ScreenId::WeeklyGrid;
+        assert_eq!(key(&app, 'k'), Some(Action::Previous));
+        assert_eq!(key(&app, ']'), Some(Action::NextWeek));
+        app.current_screen = ScreenId::Home;
+        assert_eq!(key(&app, 'j'), None);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(action::from_key(&app, esc), Some(Action::Back));
+
+        app.current_mode = AppMode::Edit;
+        assert_eq!(key(&app, 'p'), Some(Action::Type('p')));
+        assert_eq!(action::from_key(&app, esc), Some(Action::SetMode(AppMode::Navigation)));
+
+        // Nothing but editing the API key before signing in
+        app.current_mode = AppMode::Navigation;
+        app.current_screen = ScreenId::Onboarding;
+        assert_eq!(key(&app, 'p'), None);
+        assert_eq!(key(&app, 'i'), Some(Action::SetMode(AppMode::Edit)));
+    }
+
+    #[test]
+    fn show() {
+        let mut app = app();
+        assert_eq!(update(&mut app, Action::Show(ScreenId::ProjectSelection)), [Effect::LoadProjects { force: false }]);
+        assert_eq!(app.current_screen, ScreenId::ProjectSelection);
+
+        assert!(update(&mut app, Action::Show(ScreenId::TimeInput)).is_empty());
+        assert_eq!(app.current_mode, AppMode::Edit);
+
+        // Home needs a workspace
+        app.config.workspace_id = None;
+        assert_eq!(update(&mut app, Action::Show(ScreenId::Home)), [Effect::LoadWorkspaces { force: false }]);
+        assert_eq!(app.current_screen, ScreenId::WorkspaceSelection);
+    }
+
+    #[test]
+    fn back_and_forward() {
+        let mut app = app();
+        update(&mut app, Action::Show(ScreenId::ProjectSelection));
+        update(&mut app, Action::Show(ScreenId::TagSelection));
+        assert_eq!(update(&mut app, Action::Back), [Effect::LoadProjects { force: false }]);
+        assert_eq!(app.current_screen, ScreenId::ProjectSelection);
+        update(&mut app, Action::Back);
+        assert_eq!(app.current_screen, ScreenId::Home);
+        // Nothing before the first screen
+        assert!(update(&mut app, Action::Back).is_empty());
+        assert_eq!(app.current_screen, ScreenId::Home);
+
+        assert_eq!(update(&mut app, Action::Forward), [Effect::LoadProjects { force: false }]);
+        assert_eq!(app.current_screen, ScreenId::ProjectSelection);
+        // Showing another screen drops the screens ahead
+        update(&mut app, Action::Show(ScreenId::WeeklyGrid));
+        update(&mut app, Action::Forward);
+        assert_eq!(app.current_screen, ScreenId::WeeklyGrid);
+        assert_eq!(app.history, [ScreenId::Home, ScreenId::ProjectSelection]);
+
+        // Applying the filter returns to the list instead of stacking it again
+        update(&mut app, Action::Show(ScreenId::TimeEntrySelection));
+        update(&mut app, Action::EditFilter);
+        assert_eq!(app.current_screen, ScreenId::TimeEntryFilter);
+        for _ in 0..7 {
+            update(&mut app, Action::Next);
+        }
+        assert_eq!(update(&mut app, Action::Confirm), [Effect::LoadTimeEntries { force: true }]);
+        assert_eq!(app.current_screen, ScreenId::TimeEntrySelection);
+        assert_eq!(app.history.last(), Some(&ScreenId::WeeklyGrid));
+    }
+
+    #[test]
+    fn history_holds_each_screen_once() {
+        let mut app = app();
+        for _ in 0..10 {
+            update(&mut app, Action::Show(ScreenId::ProjectSelection));
+            update(&mut app, Action::Show(ScreenId::Home));
+        }
+        assert!(app.history.is_empty());
+        update(&mut app, Action::Show(ScreenId::ProjectSelection));
+        update(&mut app, Action::Show(ScreenId::TagSelection));
+        update(&mut app, Action::Show(ScreenId::ProjectSelection));
+        assert_eq!(app.history, [ScreenId::Home]);
+
+        // Home without a workspace is replaced by the workspace selection
+        app.config.workspace_id = None;
+        update(&mut app, Action::Show(ScreenId::Home));
+        assert_eq!(app.current_screen, ScreenId::WorkspaceSelection);
+        assert!(app.history.is_empty());
+        update(&mut app, Action::Show(ScreenId::TagSelection));
+        update(&mut app, Action::Back);
+        assert_eq!(app.current_screen, ScreenId::WorkspaceSelection);
+    }
+
+    #[test]
+    fn lists() {
+        let mut app = app();
+        app.projects.items = vec![project("p1", "Website"), project("p2", "Internal")];
+        update(&mut app, Action::Show(ScreenId::ProjectSelection));
+        for action in [Action::Next, Action::Next, Action::Confirm] {
+            assert!(update(&mut app, action).is_empty());
+        }
+        assert_eq!(app.projects.selected, ["p2"]);
+        update(&mut app, Action::Clear);
+        assert!(app.projects.selected.is_empty());
+
+        update(&mut app, Action::SetMode(AppMode::Search));
+        for c in "web".chars() {
+            update(&mut app, Action::Type(c));
+        }
+        assert_eq!(app.projects.search_text, "web");
+        assert_eq!(app.projects.get_highlighted_item().map(|p| p.id()).as_deref(), Some("p1"));
+        update(&mut app, Action::Backspace);
+        assert_eq!(app.projects.search_text, "we");
+
+        assert_eq!(update(&mut app, Action::Refresh), [Effect::LoadProjects { force: true }]);
+    }
+
+    #[test]
+    fn timer() {
+        let mut app = app();
+        assert_eq!(update(&mut app, Action::StartTimer), [Effect::StartTimer]);
+        assert_eq!(update(&mut app, Action::StopTimer), [Effect::StopTimer]);
+        assert!(app.current_entry_id.is_none());
+
+        // Nothing highlighted to continue
+        app.current_screen = ScreenId::TimeEntrySelection;
+        assert!(update(&mut app, Action::ContinueEntry).is_empty());
+
+        app.current_screen = ScreenId::TimeInput;
+        app.time_input.text = String::from("not a time");
+        assert!(update(&mut app, Action::Confirm).is_empty());
+        assert!(app.message.as_deref().is_some_and(|m| m.starts_with("Not understood")));
+    }
+
+    #[test]
+    fn grid() {
+        let mut app = app();
+        let row = GridRow { project_id: Some(String::from("p1")), task_id: None };
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        app.grid.set_week(monday);
+        app.grid.rows = vec![row.clone()];
+        app.current_screen = ScreenId::WeeklyGrid;
+
+        update(&mut app, Action::Right);
+        update(&mut app, Action::Next);
+        assert_eq!((app.grid.row, app.grid.column), (0, 1));
+        update(&mut app, Action::SetMode(AppMode::Edit));
+        for c in "1:30".chars() {
+            update(&mut app, Action::Type(c));
+        }
+        let effects = update(&mut app, Action::Confirm);
+        assert_eq!(effects, [Effect::SetDuration(row, monday.succ_opt().unwrap(), Duration::minutes(90))]);
+        assert_eq!(app.current_mode, AppMode::Navigation);
+
+        update(&mut app, Action::SetMode(AppMode::Edit));
+        assert!(app.grid.input.is_empty());
+        update(&mut app, Action::Type('x'));
+        assert!(update(&mut app, Action::Confirm).is_empty());
+        assert_eq!(app.grid.message.as_deref(), Some("Invalid duration: x"));
+
+        assert_eq!(update(&mut app, Action::PreviousWeek), [Effect::LoadGrid { force: true }]);
+        assert_eq!(app.grid.week_start, monday.checked_sub_days(chrono::Days::new(7)));
+        assert!(app.grid.message.is_none());
+    }
+
+    #[test]
+    fn filter() {
+        let mut app = app();
+        app.current_screen = ScreenId::TimeEntrySelection;
+        update(&mut app, Action::EditFilter);
+        assert_eq!(app.current_screen, ScreenId::TimeEntryFilter);
+
+        update(&mut app, Action::SetMode(AppMode::Edit));
+        for c in "2024-3".chars() {
+            update(&mut app, Action::Type(c));
+        }
+        update(&mut app, Action::SetMode(AppMode::Navigation));
+        for _ in 0..5 {
+            update(&mut app, Action::Next);
+        }
+        assert_eq!(app.filter_form.highlighted(), FilterField::Billable);
+        update(&mut app, Action::Confirm);
+        assert_eq!(app.filter_form.filter.billable, Some(true));
+
+        update(&mut app, Action::Previous);
+        for _ in 0..3 {
+            update(&mut app, Action::Next);
+        }
+        assert_eq!(app.filter_form.highlighted(), FilterField::Apply);
+        assert!(update(&mut app, Action::Confirm).is_empty());
+        assert!(app.filter_form.error.is_some());
+
+        update(&mut app, Action::Clear);
+        assert_eq!(update(&mut app, Action::Confirm), [Effect::LoadTimeEntries { force: true }]);
+        assert_eq!(app.current_screen, ScreenId::TimeEntrySelection);
+        assert!(app.time_entry_filter.is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use clockify::{
+    api::{
+        client::ClockifyClient,
+        nonblocking::AsyncEndPoint,
+        project::Project,
+        tag::Tag,
+        task::Task,
+        time_entry::{format_timestamp, TimeEntry},
+        workspace::Workspace,
+        EndPoint,
+        EndpointError,
+        EndpointParameters,
+    },
+    clockify::Config,
+    error::Error,
+};
+use common::{FakeClockify, USER_ID, WORKSPACE_ID};
+use reqwest::blocking::Client;
+
+fn entry(description: &str, project_id: &str, start: chrono::DateTime<Utc>, minutes: i64) -> TimeEntry {
+    TimeEntry {
+        start: Some(format_timestamp(start)),
+        end: Some(format_timestamp(start + Duration::minutes(minutes))),
+        description: Some(description.to_owned()),
+        project_id: Some(project_id.to_owned()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn lists() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let mut config = server.config();
+
+    let workspaces = Workspace::list(&client, &config, None).unwrap();
+    assert_eq!(workspaces.len(), 1);
+    assert_eq!(workspaces[0].name, "Acme");
+
+    let projects = Project::list(&client, &config, None).unwrap();
+    let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Website", "Internal"]);
+
+    config.project_id = Some(String::from("p1"));
+    let tasks = Task::list(&client, &config, None).unwrap();
+    assert_eq!(tasks.len(), 2);
+
+    let tags = Tag::list(&client, &config, None).unwrap();
+    assert_eq!(tags[1].name, "review");
+}
+
+#[test]
+fn project_crud() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let config = server.config();
+
+    let project = Project::from("Research").create(&client, &config, None).unwrap();
+    let id = project.id.clone().unwrap();
+    assert_eq!(Project::get(&client, &config, &id, None).unwrap().name, "Research");
+
+    let renamed = Project { name: String::from("Research 2"), ..project };
+    let updated = Project::update(renamed, &client, &config, &id, None).unwrap();
+    assert_eq!(updated.name, "Research 2");
+
+    Project::delete(&client, &config, &id, None).unwrap();
+    match Project::get(&client, &config, &id, None) {
+        Err(Error::Api(EndpointError::NotFound)) => {},
+        other => panic!("expected NotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn error_bodies() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let config = server.config();
+
+    match Project::from("Website").create(&client, &config, None) {
+        Err(Error::Api(EndpointError::BadRequest(Some(message)))) => {
+            assert_eq!(message.message, "Project with name already exists");
+            assert_eq!(message.code, 501);
+        },
+        other => panic!("expected BadRequest, got {:?}", other),
+    }
+
+    let config = Config { api_key: Some(String::from("wrong")), ..server.config() };
+    match Project::list(&client, &config, None) {
+        Err(Error::Api(EndpointError::Unauthorized)) => {},
+        other => panic!("expected Unauthorized, got {:?}", other),
+    }
+}
+
+#[test]
+fn time_entry_filters() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let config = server.config();
+    let monday = Utc.with_ymd_and_hms(2024, 3, 11, 9, 0, 0).unwrap();
+    entry("Sketches", "p1", monday, 60).add(&client, &config).unwrap();
+    entry("Standup", "p2", monday + Duration::days(1), 15).add(&client, &config).unwrap();
+    entry("Review & fixes", "p1", monday + Duration::days(2), 90).add(&client, &config).unwrap();
+
+    let all = TimeEntry::list(&client, &config, None).unwrap();
+    let descriptions: Vec<String> = all.iter().map(|e| e.to_string()).collect();
+    assert_eq!(descriptions, ["Review & fixes", "Standup", "Sketches"]);
+
+    let params = EndpointParameters::new()
+        .with("project", "p1")
+        .with("start", monday + Duration::hours(1))
+        .with("hydrated", true);
+    let filtered = TimeEntry::list(&client, &config, Some(params)).unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].description.as_deref(), Some("Review & fixes"));
+    assert_eq!(filtered[0].project.as_ref().unwrap().name, "Website");
+    assert_eq!(filtered[0].duration(), Some(Duration::minutes(90)));
+}
+
+#[test]
+fn stop_timer() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let config = server.config();
+    let start = Utc::now() - Duration::minutes(30);
+
+    assert!(TimeEntry::stop(&client, &config, Utc::now()).unwrap().is_none());
+
+    let running = TimeEntry { start: Some(format_timestamp(start)), ..Default::default() };
+    let running = running.create(&client, &config, None).unwrap();
+    assert!(running.is_running());
+
+    let stopped = TimeEntry::stop(&client, &config, start + Duration::minutes(30)).unwrap().unwrap();
+    assert_eq!(stopped.id, running.id);
+    assert_eq!(stopped.duration(), Some(Duration::minutes(30)));
+    assert!(server.data().running_entry().is_none());
+
+    // Not "no timer running" when the workspace does not exist
+    let config = Config { workspace_id: Some(String::from("nope")), ..config };
+    assert!(TimeEntry::stop(&client, &config, Utc::now()).is_err());
+}
+
+#[test]
+fn scoped_client() {
+    let server = FakeClockify::start();
+    let client = ClockifyClient::builder()
+        .base_url(&server.url)
+        .api_key(common::API_KEY)
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    assert_eq!(client.user().unwrap().id.as_deref(), Some(USER_ID));
+    let workspace = client.workspace(WORKSPACE_ID);
+    assert_eq!(workspace.projects().list(None).unwrap().len(), 2);
+    assert_eq!(workspace.project("p1").tasks().get("t2").unwrap().name, "Build");
+
+    let time_entries = workspace.time_entries(USER_ID);
+    let start = Utc::now() - Duration::minutes(5);
+    time_entries.create(TimeEntry { start: Some(format_timestamp(start)), ..Default::default() }).unwrap();
+    assert!(time_entries.stop(Utc::now()).unwrap().is_some());
+    assert_eq!(time_entries.list(None).unwrap().len(), 1);
+
+    match client.workspace("other").tags().list(None) {
+        Err(Error::Api(EndpointError::Forbidden)) => {},
+        other => panic!("expected Forbidden, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn async_endpoints() {
+    let server = FakeClockify::start();
+    let client = reqwest::Client::new();
+    let config = server.config();
+
+    let tag = Tag::from("planning").create_async(&client, &config, None).await.unwrap();
+    let tags = Tag::list_async(&client, &config, None).await.unwrap();
+    assert_eq!(tags.len(), 3);
+
+    let id = tag.id.unwrap();
+    Tag::delete_async(&client, &config, &id, None).await.unwrap();
+    assert_eq!(Tag::list_async(&client, &config, None).await.unwrap().len(), 2);
+
+    let start = Utc::now() - Duration::minutes(10);
+    TimeEntry { start: Some(format_timestamp(start)), ..Default::default() }
+        .create_async(&client, &config, None)
+        .await
+        .unwrap();
+    assert!(TimeEntry::stop_async(&client, &config, Utc::now()).await.unwrap().is_some());
+    assert!(TimeEntry::stop_async(&client, &config, Utc::now()).await.unwrap().is_none());
+}
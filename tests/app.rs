@@ -0,0 +1,188 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use clockify::{
+    api::{project::Project, tag::Tag, time_entry::TimeEntry, EndPoint},
+    clockify::{App, Config, ConfigFile},
+    state::State,
+    time_parser::TimeExpr,
+};
+use common::FakeClockify;
+use reqwest::blocking::Client;
+
+fn app(server: &FakeClockify, profile: &str) -> App<'static> {
+    let mut app = App::new("Clockify", ConfigFile::default(), profile.to_owned());
+    app.config = server.config();
+    app
+}
+
+#[test]
+fn start_and_stop() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let mut app = app(&server, "start-and-stop");
+    app.apply_selections(&client, Some("website"), Some("Design"), &[String::from("meeting")], Some(String::from("Kickoff"))).unwrap();
+
+    app.start_entry(&client).unwrap();
+    let running = server.data().running_entry().cloned().unwrap();
+    assert_eq!(running["description"], "Kickoff");
+    assert_eq!(running["projectId"], "p1");
+    assert_eq!(running["taskId"], "t1");
+    assert_eq!(running["tagIds"][0], "g1");
+    assert_eq!(app.running_entry_id.as_deref(), running["id"].as_str());
+    // Saved in the cache directory of the config
+    let state = State::load(Some(&server.cache_dir), "start-and-stop").unwrap();
+    assert_eq!(state.running.unwrap().description, "Kickoff");
+
+    let stopped = app.stop_entry(&client).unwrap().unwrap();
+    assert_eq!(stopped.id.as_deref(), running["id"].as_str());
+    assert!(!stopped.is_running());
+    assert!(app.running_entry_id.is_none());
+    assert!(server.data().running_entry().is_none());
+
+    assert!(app.stop_entry(&client).unwrap().is_none());
+}
+
+#[test]
+fn add_finished_entry() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let mut app = app(&server, "add-finished-entry");
+    app.description.text = String::from("Planning");
+    let start = Utc.with_ymd_and_hms(2024, 3, 11, 14, 0, 0).unwrap();
+
+    app.start_entry_at(&client, TimeExpr::Range(start, start + Duration::minutes(90))).unwrap();
+    assert!(app.running_entry_id.is_none());
+    let data = server.data();
+    let entry = data.time_entry(app.current_entry_id.as_deref().unwrap()).unwrap();
+    assert_eq!(entry["timeInterval"]["start"], "2024-03-11T14:00:00Z");
+    assert_eq!(entry["timeInterval"]["end"], "2024-03-11T15:30:00Z");
+}
+
+#[test]
+fn update_entry() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let mut app = app(&server, "update-entry");
+    app.projects.items = Project::list(&client, &app.config, None).unwrap();
+    app.tags.items = Tag::list(&client, &app.config, None).unwrap();
+    app.description.text = String::from("Draft");
+    let start = Utc.with_ymd_and_hms(2024, 3, 12, 9, 0, 0).unwrap();
+    app.start_entry_at(&client, TimeExpr::Range(start, start + Duration::hours(1))).unwrap();
+
+    app.description.text = String::from("Final copy");
+    app.projects.selected = vec![String::from("p2")];
+    app.tags.selected = vec![String::from("g2")];
+    app.update_entry(&client).unwrap();
+
+    let data = server.data();
+    let entry = data.time_entry(app.current_entry_id.as_deref().unwrap()).unwrap();
+    assert_eq!(entry["description"], "Final copy");
+    assert_eq!(entry["projectId"], "p2");
+    assert_eq!(entry["tagIds"][0], "g2");
+    // The times are kept
+    assert_eq!(entry["timeInterval"]["start"], "2024-03-12T09:00:00Z");
+    assert_eq!(entry["timeInterval"]["end"], "2024-03-12T10:00:00Z");
+    assert!(data.requests.iter().any(|(method, url)| method == "PUT" && url.ends_with(&format!("/time-entries/{}", entry["id"].as_str().unwrap()))));
+}
+
+#[test]
+fn continue_entry() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let mut app = app(&server, "continue-entry");
+    app.projects.items = Project::list(&client, &app.config, None).unwrap();
+    app.projects.selected = vec![String::from("p1")];
+    app.description.text = String::from("Coding");
+    app.start_entry(&client).unwrap();
+    let first = app.get_current_entry(&client).unwrap().unwrap();
+
+    app.stop_entry(&client).unwrap();
+    let continued = app.continue_entry(&client, &first).unwrap();
+    assert_ne!(continued.id, first.id);
+    assert_eq!(continued.description.as_deref(), Some("Coding"));
+    assert_eq!(continued.project_id.as_deref(), Some("p1"));
+    assert_eq!(app.running_entry_id, continued.id);
+
+    let data = server.data();
+    assert_eq!(data.time_entries.len(), 2);
+    assert_eq!(data.running_entry().unwrap()["id"].as_str(), continued.id.as_deref());
+
+    // An entry without project, task or tags clears the previous selection
+    app.tasks.selected = vec![String::from("t1")];
+    app.tags.selected = vec![String::from("g1")];
+    app.select_entry(&TimeEntry { description: Some(String::from("Reading")), ..Default::default() });
+    assert!(app.projects.selected.is_empty());
+    assert!(app.tasks.selected.is_empty());
+    assert!(app.tags.selected.is_empty());
+    assert_eq!(app.description.text, "Reading");
+}
+
+#[test]
+fn git_branch_task() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    server.data().tasks.push(serde_json::json!({ "id": "t3", "name": "WEB-12 Landing page", "projectId": "p2", "status": "ACTIVE", "billable": false }));
+    let mut app = app(&server, "git-branch-task");
+    app.config.branch_task = true;
+    app.git_branch = Some(String::from("feature/WEB-12-landing-page"));
+
+    // No project selected, no task even with a saved project
+    app.config.project_id = Some(String::from("p1"));
+    app.apply_git_branch(&client).unwrap();
+    assert!(app.tasks.selected.is_empty());
+
+    // Tasks of the selected project, not of the saved one
+    app.projects.selected = vec![String::from("p2")];
+    app.apply_git_branch(&client).unwrap();
+    assert_eq!(app.tasks.selected, ["t3"]);
+    assert_eq!(app.config.project_id.as_deref(), Some("p2"));
+    assert_eq!(app.description.text, "");
+}
+
+#[test]
+fn unknown_names() {
+    let server = FakeClockify::start();
+    let client = Client::new();
+    let mut app = app(&server, "unknown-names");
+    assert!(app.apply_selections(&client, Some("Nope"), None, &[], None).is_err());
+    assert!(app.apply_selections(&client, Some("Website"), Some("Nope"), &[], None).is_err());
+    app.apply_selections(&client, Some("p2"), None, &[], None).unwrap();
+    assert_eq!(app.projects.selected, ["p2"]);
+    assert_eq!(app.config.project_id.as_deref(), Some("p2"));
+    assert!(app.tasks.items.is_empty());
+}
+
+#[test]
+fn switch_profile_key() {
+    let server = FakeClockify::start();
+    let mut config_file = ConfigFile::default();
+    let acme = Config { api_key: None, api_key_command: Some(String::from("echo acme-key")), ..server.config() };
+    config_file.profiles.insert(String::from("acme"), acme);
+    config_file.profiles.insert(String::from("work"), server.config());
+    let mut app = App::new("Clockify", config_file, String::from("work"));
+
+    // Another profile uses its own command, never the key of the first one
+    app.switch_profile("acme");
+    assert_eq!(app.profile, "acme");
+    assert_eq!(app.startup_profile, "work");
+    assert_eq!(app.config.api_key.as_deref(), Some("acme-key"));
+    app.switch_profile("work");
+    assert_eq!(app.config.api_key.as_deref(), Some(common::API_KEY));
+}
+
+#[test]
+fn completions_without_config() {
+    let home = std::env::temp_dir().join(format!("clockify-home-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&home);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_clockify"))
+        .args(["completions", "bash"])
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("complete -F"));
+    // The config file is neither read nor created
+    assert!(!home.exists());
+}
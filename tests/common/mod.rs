@@ -0,0 +1,366 @@
+// In-process fake of the parts of the Clockify API the crate uses. It keeps
+// workspaces, the user, projects, tasks, tags and time entries in memory and
+// answers like Clockify does, including its error bodies.
+#![allow(dead_code)]
+
+use std::{
+    path::PathBuf,
+    sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex},
+    thread,
+};
+
+use chrono::{DateTime, Utc};
+use clockify::clockify::Config;
+use percent_encoding::percent_decode_str;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub const API_KEY: &str = "test-key";
+pub const WORKSPACE_ID: &str = "ws1";
+pub const USER_ID: &str = "user1";
+
+#[derive(Debug, Default)]
+pub struct Data {
+    pub workspaces: Vec<Value>,
+    pub user: Value,
+    pub projects: Vec<Value>,
+    pub tasks: Vec<Value>,
+    pub tags: Vec<Value>,
+    pub time_entries: Vec<Value>,
+    // Method and URL of every request
+    pub requests: Vec<(String, String)>,
+    next_id: u32,
+}
+
+impl Data {
+    fn seeded() -> Data {
+        Data {
+            workspaces: vec![json!({ "id": WORKSPACE_ID, "name": "Acme" })],
+            user: json!({
+                "id": USER_ID,
+                "email": "dev@example.com",
+                "name": "Dev",
+                "activeWorkspace": WORKSPACE_ID,
+                "defaultWorkspace": WORKSPACE_ID,
+                "settings": { "timeZone": "UTC", "weekStart": "MONDAY", "timeFormat": "HOUR24", "dateFormat": "YYYY-MM-DD" },
+            }),
+            projects: vec![
+                json!({ "id": "p1", "name": "Website", "workspaceId": WORKSPACE_ID, "billable": true, "archived": false }),
+                json!({ "id": "p2", "name": "Internal", "workspaceId": WORKSPACE_ID, "billable": false, "archived": false }),
+            ],
+            tasks: vec![
+                json!({ "id": "t1", "name": "Design", "projectId": "p1", "status": "ACTIVE", "billable": true }),
+                json!({ "id": "t2", "name": "Build", "projectId": "p1", "status": "ACTIVE", "billable": true }),
+            ],
+            tags: vec![
+                json!({ "id": "g1", "name": "meeting", "workspaceId": WORKSPACE_ID, "archived": false }),
+                json!({ "id": "g2", "name": "review", "workspaceId": WORKSPACE_ID, "archived": false }),
+            ],
+            time_entries: vec![],
+            requests: vec![],
+            next_id: 0,
+        }
+    }
+
+    fn new_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}-{}", prefix, self.next_id)
+    }
+
+    pub fn running_entry(&self) -> Option<&Value> {
+        self.time_entries.iter().find(|e| e["timeInterval"]["end"].is_null())
+    }
+
+    pub fn time_entry(&self, id: &str) -> Option<&Value> {
+        self.time_entries.iter().find(|e| e["id"] == id)
+    }
+}
+
+pub struct FakeClockify {
+    pub url: String,
+    pub data: Arc<Mutex<Data>>,
+    // Where the app saves its state files
+    pub cache_dir: PathBuf,
+}
+
+impl FakeClockify {
+    pub fn start() -> FakeClockify {
+        static SERVERS: AtomicUsize = AtomicUsize::new(0);
+        let cache_dir = std::env::temp_dir()
+            .join(format!("clockify-tests-{}-{}", std::process::id(), SERVERS.fetch_add(1, Ordering::SeqCst)));
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/v1", server.server_addr().to_ip().unwrap());
+        let data = Arc::new(Mutex::new(Data::seeded()));
+        let shared = data.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle(request, &shared);
+            }
+        });
+        FakeClockify { url, data, cache_dir }
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            base_url: self.url.clone(),
+            api_key: Some(API_KEY.to_owned()),
+            workspace_id: Some(WORKSPACE_ID.to_owned()),
+            user_id: Some(USER_ID.to_owned()),
+            cache_dir: Some(self.cache_dir.clone()),
+            ..Default::default()
+        }
+    }
+
+    pub fn data(&self) -> std::sync::MutexGuard<'_, Data> {
+        self.data.lock().unwrap()
+    }
+}
+
+impl Drop for FakeClockify {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.cache_dir);
+    }
+}
+
+type Reply = (u16, Value);
+
+fn error(status: u16, message: &str, code: u32) -> Reply {
+    (status, json!({ "message": message, "code": code }))
+}
+
+fn not_found() -> Reply {
+    error(404, "Not found", 404)
+}
+
+fn handle(mut request: Request, data: &Mutex<Data>) {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body).unwrap();
+    let authorized = request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("X-API-KEY") && h.value.as_str() == API_KEY);
+    let (status, value) = if authorized {
+        let body = serde_json::from_str(&body).unwrap_or(Value::Null);
+        let mut data = data.lock().unwrap();
+        data.requests.push((request.method().to_string(), request.url().to_owned()));
+        route(&mut data, request.method(), request.url(), body)
+    } else {
+        error(401, "Full authentication is required to access this resource", 1000)
+    };
+    let response = Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    let _ = request.respond(response);
+}
+
+fn route(data: &mut Data, method: &Method, url: &str, body: Value) -> Reply {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = parse_query(query);
+    let path = match path.strip_prefix("/api/v1") {
+        Some(path) => path,
+        None => return not_found(),
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["user"]) => (200, data.user.clone()),
+        (Method::Get, ["workspaces"]) => (200, json!(data.workspaces)),
+        (_, ["workspaces", ws, ..]) if *ws != WORKSPACE_ID => error(403, "Access denied", 403),
+        (Method::Get, ["workspaces", _, "users"]) => (200, json!([data.user])),
+
+        (Method::Get, ["workspaces", _, "projects"]) => (200, json!(data.projects)),
+        (Method::Post, ["workspaces", ws, "projects"]) => {
+            let name = body["name"].as_str().unwrap_or_default();
+            if data.projects.iter().any(|p| p["name"] == name) {
+                return error(400, "Project with name already exists", 501);
+            }
+            let mut project = body.clone();
+            project["id"] = json!(data.new_id("p"));
+            project["workspaceId"] = json!(ws);
+            data.projects.push(project.clone());
+            (201, project)
+        },
+        (_, ["workspaces", _, "projects", id]) => item(&mut data.projects, method, id, body),
+
+        (Method::Get, ["workspaces", _, "projects", project, "tasks"]) => {
+            if !data.projects.iter().any(|p| p["id"] == *project) {
+                return not_found();
+            }
+            let tasks: Vec<&Value> = data.tasks.iter().filter(|t| t["projectId"] == *project).collect();
+            (200, json!(tasks))
+        },
+        (Method::Post, ["workspaces", _, "projects", project, "tasks"]) => {
+            let mut task = body.clone();
+            task["id"] = json!(data.new_id("t"));
+            task["projectId"] = json!(project);
+            task["status"] = json!("ACTIVE");
+            task["billable"] = json!(task["billable"].as_bool().unwrap_or(false));
+            data.tasks.push(task.clone());
+            (201, task)
+        },
+        (_, ["workspaces", _, "projects", _, "tasks", id]) => item(&mut data.tasks, method, id, body),
+
+        (Method::Get, ["workspaces", _, "tags"]) => (200, json!(data.tags)),
+        (Method::Post, ["workspaces", ws, "tags"]) => {
+            let mut tag = body.clone();
+            tag["id"] = json!(data.new_id("g"));
+            tag["workspaceId"] = json!(ws);
+            data.tags.push(tag.clone());
+            (201, tag)
+        },
+        (_, ["workspaces", _, "tags", id]) => item(&mut data.tags, method, id, body),
+
+        (_, ["workspaces", _, "user", user, "time-entries"]) if *user != USER_ID => not_found(),
+        (Method::Get, ["workspaces", _, "user", _, "time-entries"]) => list_time_entries(data, &query),
+        (Method::Post, ["workspaces", _, "user", _, "time-entries"]) => create_time_entry(data, body),
+        (Method::Patch, ["workspaces", _, "user", _, "time-entries"]) => stop_timer(data, body),
+        (Method::Get, ["workspaces", _, "time-entries", id]) => match data.time_entry(id) {
+            Some(entry) => (200, entry.clone()),
+            None => not_found(),
+        },
+        (Method::Put, ["workspaces", _, "time-entries", id]) => update_time_entry(data, id, body),
+        (Method::Delete, ["workspaces", _, "time-entries", id]) => item(&mut data.time_entries, method, id, body),
+
+        _ => not_found(),
+    }
+}
+
+// GET, PUT and DELETE of a single item
+fn item(items: &mut Vec<Value>, method: &Method, id: &str, body: Value) -> Reply {
+    let index = match items.iter().position(|item| item["id"] == id) {
+        Some(index) => index,
+        None => return not_found(),
+    };
+    match method {
+        Method::Get => (200, items[index].clone()),
+        Method::Put => {
+            let mut updated = body;
+            updated["id"] = json!(id);
+            items[index] = updated.clone();
+            (200, updated)
+        },
+        Method::Delete => {
+            items.remove(index);
+            (200, Value::Null)
+        },
+        _ => error(405, "Method not allowed", 405),
+    }
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                percent_decode_str(key).decode_utf8_lossy().into_owned(),
+                percent_decode_str(value).decode_utf8_lossy().into_owned(),
+            )
+        })
+        .collect()
+}
+
+fn param<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    query.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.as_str()?).ok().map(|t| t.with_timezone(&Utc))
+}
+
+fn time_interval(start: &Value, end: &Value) -> Value {
+    let duration = match (timestamp(start), timestamp(end)) {
+        (Some(start), Some(end)) => {
+            let seconds = (end - start).num_seconds();
+            json!(format!("PT{}H{}M{}S", seconds / 3600, seconds % 3600 / 60, seconds % 60))
+        },
+        _ => Value::Null,
+    };
+    json!({ "start": start, "end": end, "duration": duration })
+}
+
+fn list_time_entries(data: &Data, query: &[(String, String)]) -> Reply {
+    let start = param(query, "start").and_then(|s| timestamp(&json!(s)));
+    let end = param(query, "end").and_then(|s| timestamp(&json!(s)));
+    let tags: Vec<&str> = query.iter().filter(|(k, _)| k == "tags").map(|(_, v)| v.as_str()).collect();
+    let mut entries: Vec<Value> = data.time_entries
+        .iter()
+        .filter(|e| param(query, "in-progress") != Some("true") || e["timeInterval"]["end"].is_null())
+        .filter(|e| param(query, "project").is_none_or(|p| e["projectId"] == p))
+        .filter(|e| param(query, "task").is_none_or(|t| e["taskId"] == t))
+        .filter(|e| tags.iter().all(|tag| e["tagIds"].as_array().is_some_and(|ids| ids.iter().any(|id| id == tag))))
+        .filter(|e| {
+            let entry_start = timestamp(&e["timeInterval"]["start"]);
+            start.is_none_or(|s| entry_start.is_some_and(|t| t >= s))
+                && end.is_none_or(|s| entry_start.is_some_and(|t| t < s))
+        })
+        .cloned()
+        .collect();
+    // Newest first, like Clockify
+    entries.sort_by_key(|e| std::cmp::Reverse(timestamp(&e["timeInterval"]["start"])));
+    if param(query, "hydrated") == Some("true") {
+        for entry in entries.iter_mut() {
+            entry["project"] = data.projects.iter().find(|p| p["id"] == entry["projectId"]).cloned().unwrap_or(Value::Null);
+            entry["task"] = data.tasks.iter().find(|t| t["id"] == entry["taskId"]).cloned().unwrap_or(Value::Null);
+        }
+    }
+    if let Some(size) = param(query, "page-size").and_then(|s| s.parse().ok()) {
+        entries.truncate(size);
+    }
+    (200, json!(entries))
+}
+
+fn create_time_entry(data: &mut Data, body: Value) -> Reply {
+    if timestamp(&body["start"]).is_none() {
+        return error(400, "start is required", 501);
+    }
+    // Starting a timer stops the running one
+    if body["end"].is_null() {
+        let start = body["start"].clone();
+        if let Some(running) = data.time_entries.iter_mut().find(|e| e["timeInterval"]["end"].is_null()) {
+            running["timeInterval"] = time_interval(&running["timeInterval"]["start"], &start);
+        }
+    }
+    let entry = json!({
+        "id": data.new_id("e"),
+        "description": body["description"].as_str().unwrap_or_default(),
+        "projectId": body["projectId"],
+        "taskId": body["taskId"],
+        "tagIds": body["tagIds"].as_array().cloned().unwrap_or_default(),
+        "billable": body["billable"].as_bool().unwrap_or(false),
+        "userId": USER_ID,
+        "workspaceId": WORKSPACE_ID,
+        "timeInterval": time_interval(&body["start"], &body["end"]),
+    });
+    data.time_entries.push(entry.clone());
+    (201, entry)
+}
+
+fn stop_timer(data: &mut Data, body: Value) -> Reply {
+    let running = data.time_entries.iter_mut().find(|e| e["timeInterval"]["end"].is_null());
+    match running {
+        Some(entry) => {
+            entry["timeInterval"] = time_interval(&entry["timeInterval"]["start"], &body["end"]);
+            (200, entry.clone())
+        },
+        None => error(404, "No running time entry", 404),
+    }
+}
+
+fn update_time_entry(data: &mut Data, id: &str, body: Value) -> Reply {
+    let entry = match data.time_entries.iter_mut().find(|e| e["id"] == id) {
+        Some(entry) => entry,
+        None => return not_found(),
+    };
+    // Start is required and fields that are left out are cleared
+    if timestamp(&body["start"]).is_none() {
+        return error(400, "start is required", 501);
+    }
+    entry["description"] = json!(body["description"].as_str().unwrap_or_default());
+    entry["projectId"] = body["projectId"].clone();
+    entry["taskId"] = body["taskId"].clone();
+    entry["tagIds"] = json!(body["tagIds"].as_array().cloned().unwrap_or_default());
+    entry["billable"] = json!(body["billable"].as_bool().unwrap_or(false));
+    entry["timeInterval"] = time_interval(&body["start"], &body["end"]);
+    (200, entry.clone())
+}
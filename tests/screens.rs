@@ -0,0 +1,306 @@
+// Renders every screen into a TestBackend after scripted keys and compares
+// the buffer with tests/snapshots/<name>.txt. Run with UPDATE_SNAPSHOTS=1 to
+// write the snapshots after an intended change to a screen.
+mod common;
+
+use std::{env, fs, path::PathBuf};
+
+use chrono::{Duration, NaiveDate, TimeZone, Utc, Weekday};
+use clockify::{
+    api::{time_entry::{format_timestamp, TimeEntry}, EndPoint},
+    clockify::{App, AppMode, Config, ConfigFile},
+    ui::{self, action::Action, ScreenId},
+};
+use common::FakeClockify;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use reqwest::blocking::Client;
+use tui::{backend::TestBackend, layout::Rect, Terminal};
+
+const WIDTH: u16 = 120;
+const HEIGHT: u16 = 30;
+
+struct Harness {
+    server: FakeClockify,
+    client: Client,
+    app: App<'static>,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    // An app signed in to the fake server with some entries in the week of
+    // 2024-03-11, showing the Home screen
+    fn new() -> Harness {
+        let server = FakeClockify::start();
+        let client = Client::new();
+        let config = server.config();
+        let entries = [
+            ("Sketches", "p1", Some("t1"), 0, 9, 90),
+            ("Standup", "p2", None, 1, 9, 15),
+            ("Landing page", "p1", Some("t2"), 1, 10, 150),
+            ("Review", "p1", Some("t2"), 2, 14, 60),
+        ];
+        for (description, project, task, day, hour, minutes) in entries {
+            let start = Utc.with_ymd_and_hms(2024, 3, 11, hour, 0, 0).unwrap() + Duration::days(day);
+            TimeEntry {
+                start: Some(format_timestamp(start)),
+                end: Some(format_timestamp(start + Duration::minutes(minutes))),
+                description: Some(description.to_owned()),
+                project_id: Some(project.to_owned()),
+                task_id: task.map(str::to_owned),
+                tag_ids: Some(vec![String::from("g1")]),
+                ..Default::default()
+            }
+            .add(&client, &config)
+            .unwrap();
+        }
+
+        let mut config_file = ConfigFile::default();
+        config_file.profiles.insert(String::from("personal"), Config::default());
+        config_file.profiles.insert(String::from("work"), config.clone());
+        let mut app = App::new("Clockify", config_file, String::from("work"));
+        app.config = Config { poll_interval: 0, ..config };
+        let mut harness = Harness {
+            server,
+            client,
+            app,
+            terminal: Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap(),
+        };
+        // Loading, then Home
+        harness.draw();
+        ui::dispatch(&harness.client, &mut harness.app, Action::Show(ScreenId::Loading)).unwrap();
+        harness.draw();
+        harness
+    }
+
+    fn draw(&mut self) {
+        ui::draw(&mut self.terminal, &self.app).unwrap();
+    }
+
+    fn key(&mut self, code: KeyCode) -> &mut Harness {
+        let key = KeyEvent::new(code, KeyModifiers::NONE);
+        ui::handle_key(&self.client, &mut self.app, key).unwrap();
+        self.draw();
+        self
+    }
+
+    // Left click at a terminal position
+    fn click(&mut self, column: u16, row: u16) -> &mut Harness {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), column, row)
+    }
+
+    fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) -> &mut Harness {
+        let mouse = MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE };
+        ui::dispatch(&self.client, &mut self.app, Action::Mouse(mouse, Rect::new(0, 0, WIDTH, HEIGHT))).unwrap();
+        self.draw();
+        self
+    }
+
+    // Each character as a key press
+    fn keys(&mut self, keys: &str) -> &mut Harness {
+        for c in keys.chars() {
+            self.key(KeyCode::Char(c));
+        }
+        self
+    }
+
+    fn rendered(&self) -> String {
+        let buffer = self.terminal.backend().buffer();
+        let mut lines = vec![];
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width).map(|x| buffer.get(x, y).symbol.clone()).collect();
+            lines.push(line.trim_end().to_owned());
+        }
+        lines.join("\n") + "\n"
+    }
+
+    fn assert_snapshot(&self, name: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{}.txt", name));
+        let rendered = self.rendered();
+        if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &rendered).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("no snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it", path.display()));
+        assert!(rendered == expected, "{} changed:\n{}\nexpected:\n{}", name, rendered, expected);
+    }
+}
+
+#[test]
+fn home() {
+    let h = Harness::new();
+    h.assert_snapshot("home");
+}
+
+#[test]
+fn home_with_selected_entry() {
+    let mut h = Harness::new();
+    h.keys("t").key(KeyCode::Down).key(KeyCode::Enter);
+    assert!(matches!(h.app.current_screen, ScreenId::Home));
+    h.assert_snapshot("home_with_selected_entry");
+}
+
+#[test]
+fn workspace_selection() {
+    let mut h = Harness::new();
+    h.keys("w");
+    h.assert_snapshot("workspace_selection");
+}
+
+#[test]
+fn time_entry_selection() {
+    let mut h = Harness::new();
+    h.keys("tj");
+    h.assert_snapshot("time_entry_selection");
+}
+
+#[test]
+fn time_entry_search() {
+    let mut h = Harness::new();
+    h.keys("t/land");
+    h.assert_snapshot("time_entry_search");
+}
+
+#[test]
+fn timesheet() {
+    let mut h = Harness::new();
+    h.keys("tv");
+    h.assert_snapshot("timesheet");
+}
+
+#[test]
+fn timesheet_click() {
+    let mut h = Harness::new();
+    h.keys("tv").click(2, 9);
+    let highlighted = h.app.time_entries.get_highlighted_item().cloned();
+    assert_eq!(highlighted.and_then(|entry| entry.description).as_deref(), Some("Landing page"));
+    // Day headers are not entries
+    h.click(2, 8);
+    let highlighted = h.app.time_entries.get_highlighted_item().cloned();
+    assert_eq!(highlighted.and_then(|entry| entry.description).as_deref(), Some("Landing page"));
+}
+
+#[test]
+fn timesheet_week_start() {
+    let mut h = Harness::new();
+    h.app.formatter.week_start = Weekday::Sun;
+    h.keys("tv");
+    assert!(h.rendered().contains("Week of 2024-03-10"));
+    assert!(!h.rendered().contains("Week of 2024-03-11"));
+}
+
+#[test]
+fn time_entry_filter() {
+    let mut h = Harness::new();
+    h.keys("tf").key(KeyCode::Down).key(KeyCode::Down);
+    h.assert_snapshot("time_entry_filter");
+}
+
+#[test]
+fn project_selection() {
+    let mut h = Harness::new();
+    h.keys("pj");
+    h.assert_snapshot("project_selection");
+}
+
+#[test]
+fn project_click() {
+    let mut h = Harness::new();
+    h.keys("p").click(10, 11);
+    assert_eq!(h.app.projects.get_highlighted_item().map(|p| p.name.as_str()), Some("Internal"));
+    // Outside the rows
+    h.click(10, 2);
+    assert_eq!(h.app.projects.get_highlighted_item().map(|p| p.name.as_str()), Some("Internal"));
+    h.click(10, 11);
+    assert_eq!(h.app.projects.selected, ["p2"]);
+    // The wheel moves the highlight like the arrow keys
+    h.mouse(MouseEventKind::ScrollUp, 10, 11);
+    assert_eq!(h.app.projects.get_highlighted_item().map(|p| p.name.as_str()), Some("Website"));
+}
+
+#[test]
+fn task_selection() {
+    let mut h = Harness::new();
+    h.keys("y");
+    h.assert_snapshot("task_selection");
+}
+
+#[test]
+fn tag_selection() {
+    let mut h = Harness::new();
+    h.keys("gj").key(KeyCode::Enter);
+    h.assert_snapshot("tag_selection");
+}
+
+#[test]
+fn description_edit() {
+    let mut h = Harness::new();
+    h.keys("di").keys("Write docs").key(KeyCode::Esc);
+    h.assert_snapshot("description_edit");
+}
+
+#[test]
+fn weekly_grid() {
+    let mut h = Harness::new();
+    h.app.grid.set_week(NaiveDate::from_ymd_opt(2024, 3, 11).unwrap());
+    h.keys("b").key(KeyCode::Right);
+    h.assert_snapshot("weekly_grid");
+}
+
+#[test]
+fn weekly_grid_input() {
+    let mut h = Harness::new();
+    h.app.grid.set_week(NaiveDate::from_ymd_opt(2024, 3, 11).unwrap());
+    h.keys("b").key(KeyCode::Down).keys("i1:45");
+    assert!(matches!(h.app.current_mode, AppMode::Edit));
+    h.assert_snapshot("weekly_grid_input");
+}
+
+#[test]
+fn profile_selection() {
+    let mut h = Harness::new();
+    h.keys("o");
+    h.assert_snapshot("profile_selection");
+}
+
+#[test]
+fn time_input() {
+    let mut h = Harness::new();
+    h.keys("m").keys("2024-03-12 14:00-15:30");
+    h.assert_snapshot("time_input");
+    h.key(KeyCode::Enter);
+    h.assert_snapshot("time_input_added");
+    assert_eq!(h.server.data().time_entries.len(), 5);
+}
+
+#[test]
+fn onboarding() {
+    let mut h = Harness::new();
+    h.app.config.api_key = None;
+    h.app.onboarding = true;
+    h.app.current_screen = ScreenId::Onboarding;
+    h.app.current_mode = AppMode::Edit;
+    h.keys("wrong-key").key(KeyCode::Enter);
+    h.assert_snapshot("onboarding_rejected");
+
+    for _ in 0.."wrong-key".len() {
+        h.key(KeyCode::Backspace);
+    }
+    h.keys(common::API_KEY);
+    h.assert_snapshot("onboarding");
+    h.key(KeyCode::Enter);
+    assert!(matches!(h.app.current_screen, ScreenId::WorkspaceSelection));
+    h.assert_snapshot("onboarding_workspace");
+}
+
+#[test]
+fn back_and_forward() {
+    let mut h = Harness::new();
+    h.keys("pg").key(KeyCode::Esc);
+    assert_eq!(h.app.current_screen, ScreenId::ProjectSelection);
+    h.key(KeyCode::Backspace);
+    assert_eq!(h.app.current_screen, ScreenId::Home);
+    h.key(KeyCode::Tab);
+    h.assert_snapshot("project_selection");
+}